@@ -0,0 +1,157 @@
+//! Saving and loading the state of unfinished renders.
+
+use crate::raytracer::Pixel;
+use crate::math::CameraSettings;
+
+use std::io::{self, Read, Write, BufReader, BufWriter};
+use std::convert::TryFrom;
+use std::path::Path;
+use std::fs::File;
+
+const MAGIC: &[u8; 8] = b"PTCKPT03";
+
+/// State of an interrupted render. Pixels are seeded deterministically from `seed`, so
+/// continuing from `passes_done` gives the same image as an uninterrupted render.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub width:       usize,
+    pub height:      usize,
+    pub samples:     usize,
+    pub passes_done: usize,
+    pub seed:        u64,
+    /// Whether the render is spectral rather than RGB.
+    pub spectral:    bool,
+    /// Hash of the scene, the files it references, camera settings and build features, see
+    /// `hash_inputs`.
+    pub inputs:      u64,
+}
+
+/// Hashes the `sources` of a render (the scene file followed by the files it references),
+/// its `camera` settings and the enabled math features, so that a checkpoint isn't resumed
+/// with a different scene or a build giving different results. Uses FNV-1a, which gives the
+/// same hash in every build.
+pub fn hash_inputs(sources: &[Vec<u8>], camera: &CameraSettings) -> u64 {
+    fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+        bytes.iter().fold(hash, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3))
+    }
+
+    let features = [cfg!(feature = "scalar") as u8, cfg!(feature = "precise") as u8];
+
+    let mut hash = fnv1a(0xcbf2_9ce4_8422_2325, &features);
+
+    for source in sources {
+        hash = fnv1a(hash, &(source.len() as u64).to_le_bytes());
+        hash = fnv1a(hash, source);
+    }
+
+    for vector in &[camera.eye, camera.target, camera.up] {
+        for component in &vector.extract_array() {
+            hash = fnv1a(hash, &component.to_le_bytes());
+        }
+    }
+
+    fnv1a(hash, &camera.fov.to_le_bytes())
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl Checkpoint {
+    /// Saves the checkpoint with its accumulation `buffer`. The file is replaced atomically
+    /// so a render killed while saving keeps the previous checkpoint.
+    pub fn save(&self, path: impl AsRef<Path>, buffer: &[Pixel]) -> io::Result<()> {
+        let path = path.as_ref();
+
+        assert!(buffer.len() == self.width * self.height,
+                "Checkpoint buffer size doesn't match its dimensions.");
+
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        {
+            let mut writer = BufWriter::new(File::create(&temporary)?);
+
+            writer.write_all(MAGIC)?;
+
+            let values = [self.width, self.height, self.samples, self.passes_done,
+                          self.spectral as usize];
+
+            for value in &values {
+                writer.write_all(&(*value as u64).to_le_bytes())?;
+            }
+
+            writer.write_all(&self.seed.to_le_bytes())?;
+            writer.write_all(&self.inputs.to_le_bytes())?;
+
+            for channel in buffer.iter().flatten() {
+                writer.write_all(&channel.to_le_bytes())?;
+            }
+
+            writer.into_inner().map_err(|err| err.into_error())?.sync_all()?;
+        }
+
+        std::fs::rename(&temporary, path)
+    }
+
+    /// Loads checkpoint and its accumulation buffer from `path`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<(Self, Vec<Pixel>)> {
+        let mut reader = BufReader::new(File::open(path)?);
+
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+
+        if &magic != MAGIC {
+            return Err(invalid("file is not a render checkpoint"));
+        }
+
+        let mut read_u64 = || -> io::Result<u64> {
+            let mut bytes = [0u8; 8];
+            reader.read_exact(&mut bytes)?;
+
+            Ok(u64::from_le_bytes(bytes))
+        };
+
+        let mut read_usize = || -> io::Result<usize> {
+            usize::try_from(read_u64()?).map_err(|_| invalid("checkpoint value is too large"))
+        };
+
+        let checkpoint = Self {
+            width:       read_usize()?,
+            height:      read_usize()?,
+            samples:     read_usize()?,
+            passes_done: read_usize()?,
+            spectral:    read_usize()? != 0,
+            seed:        read_u64()?,
+            inputs:      read_u64()?,
+        };
+
+        if checkpoint.passes_done > checkpoint.samples {
+            return Err(invalid("checkpoint has more passes done than it has samples"));
+        }
+
+        let pixel_count = checkpoint.width.checked_mul(checkpoint.height)
+            .ok_or_else(|| invalid("checkpoint dimensions are too large"))?;
+
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        if Some(data.len()) != pixel_count.checked_mul(std::mem::size_of::<Pixel>()) {
+            return Err(invalid("checkpoint size doesn't match its dimensions"));
+        }
+
+        let buffer = data.chunks_exact(std::mem::size_of::<Pixel>())
+            .map(|pixel| {
+                let channel = |index: usize| {
+                    let bytes = &pixel[index * 4..index * 4 + 4];
+
+                    f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+                };
+
+                [channel(0), channel(1), channel(2)]
+            })
+            .collect();
+
+        Ok((checkpoint, buffer))
+    }
+}
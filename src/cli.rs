@@ -0,0 +1,289 @@
+use path_tracer::Vec3;
+use path_tracer::output::{DisplayTransform, Tonemap};
+use path_tracer::scene::BvhWidth;
+
+const USAGE: &str = "\
+Usage: path-tracer [OPTIONS]
+
+Options:
+  -W, --width <PIXELS>     Width of the output image [default: 3640]
+  -H, --height <PIXELS>    Height of the output image [default: 1960]
+  -s, --samples <COUNT>    Samples per pixel axis, COUNT^2 rays per pixel [default: 16]
+  -S, --scene <SCENE>      Built-in scene name (simple, random, cornell) or path to a
+                           scene file [default: simple]
+  -o, --output <PATH>      Path of the output image, can be given multiple times. Format
+                           is chosen by extension: .exr, .pfm and .hdr keep linear
+                           radiance, other formats (e.g. .png) are display referred
+                           [default: output.png]
+      --preview <PATH>     Periodically save the image rendered so far to PATH
+      --preview-interval <SECONDS>
+                           Minimum time between preview saves [default: 30]
+      --seed <SEED>        Seed of the random number generator [default: random]
+      --checkpoint <PATH>  Periodically save the render state to PATH so it can be resumed
+      --checkpoint-interval <SECONDS>
+                           Minimum time between checkpoint saves [default: 300]
+      --resume             Continue the render saved in the checkpoint file, options,
+                           scene files and build features affecting the image must
+                           match the interrupted render
+      --exposure <STOPS>   Exposure adjustment applied to display referred outputs
+                           [default: 0]
+      --tonemap <OPERATOR> Tone mapping operator for display referred outputs: clamp,
+                           reinhard, extended-reinhard, aces or agx [default: clamp]
+      --white <VALUE>      White point of the extended-reinhard operator [default: 4]
+      --eye <X,Y,Z>        Camera position [default: 12,2,3]
+      --target <X,Y,Z>     Point the camera looks at [default: 0,0,0]
+      --up <X,Y,Z>         Camera up vector [default: 0,1,0]
+      --fov <DEGREES>      Vertical field of view [default: 20]
+      --accel <STRUCTURE>  Acceleration structure: bvh2, bvh4 or bvh8, all produce the
+                           same image [default: bvh8]
+      --spectral           Render spectrally, needed for dispersion in dielectrics. Colors
+                           of dispersed light are mixed in RGB, not per wavelength
+  -h, --help               Print this help message";
+
+pub const SCENES: &[&str] = &["simple", "random", "cornell"];
+
+pub struct CameraOptions {
+    pub eye:    Option<Vec3>,
+    pub target: Option<Vec3>,
+    pub up:     Option<Vec3>,
+    pub fov:    Option<f32>,
+}
+
+pub struct PreviewOptions {
+    pub path:     String,
+    pub interval: f64,
+}
+
+pub struct CheckpointOptions {
+    pub path:     String,
+    pub interval: f64,
+    pub resume:   bool,
+}
+
+pub struct Options {
+    pub width:      usize,
+    pub height:     usize,
+    pub samples:    usize,
+    pub scene:      String,
+    pub outputs:    Vec<String>,
+    pub preview:    Option<PreviewOptions>,
+    pub checkpoint: Option<CheckpointOptions>,
+    pub seed:       Option<u64>,
+    pub display:    DisplayTransform,
+    pub camera:     CameraOptions,
+    pub accel:      BvhWidth,
+    pub spectral:   bool,
+}
+
+pub enum Command {
+    Render(Box<Options>),
+    Help,
+}
+
+pub fn usage() -> &'static str {
+    USAGE
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid value `{}` for `{}`", value, option))
+}
+
+fn parse_positive(option: &str, value: &str) -> Result<usize, String> {
+    match parse_number(option, value)? {
+        0     => Err(format!("`{}` must be greater than zero", option)),
+        value => Ok(value),
+    }
+}
+
+fn parse_vector(option: &str, value: &str) -> Result<Vec3, String> {
+    let components: Vec<&str> = value.split(',').map(|c| c.trim()).collect();
+
+    if components.len() != 3 {
+        return Err(format!("`{}` expects a vector in the X,Y,Z form, got `{}`", option, value));
+    }
+
+    let mut parsed = [0.0f32; 3];
+
+    for (component, string) in parsed.iter_mut().zip(components) {
+        *component = parse_number(option, string)?;
+
+        if !component.is_finite() {
+            return Err(format!("`{}` must have finite components", option));
+        }
+    }
+
+    Ok(Vec3::new(parsed[0], parsed[1], parsed[2]))
+}
+
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut options = Options {
+        width:      3840 - 200,
+        height:     2160 - 200,
+        samples:    16,
+        scene:      String::from("simple"),
+        outputs:    Vec::new(),
+        preview:    None,
+        checkpoint: None,
+        seed:       None,
+        display:    DisplayTransform::default(),
+        camera:     CameraOptions {
+            eye:    None,
+            target: None,
+            up:     None,
+            fov:    None,
+        },
+        accel:      BvhWidth::Eight,
+        spectral:   false,
+    };
+
+    let mut tonemap             = String::from("clamp");
+    let mut accel               = String::from("bvh8");
+    let mut white               = 4.0f32;
+    let mut preview             = None;
+    let mut preview_interval    = 30.0f64;
+    let mut checkpoint          = None;
+    let mut checkpoint_interval = 300.0f64;
+    let mut resume              = false;
+
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(Command::Help);
+        }
+
+        if arg == "--resume" {
+            resume = true;
+            continue;
+        }
+
+        if arg == "--spectral" {
+            options.spectral = true;
+            continue;
+        }
+
+        // Accept both `--option value` and `--option=value` forms.
+        let (option, inline_value) = match arg.find('=') {
+            Some(index) if arg.starts_with("--") => {
+                (arg[..index].to_string(), Some(arg[index + 1..].to_string()))
+            }
+            _ => (arg, None),
+        };
+
+        let mut value = || {
+            inline_value.clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("missing value for `{}`", option))
+        };
+
+        match option.as_str() {
+            "-W" | "--width"   => options.width   = parse_positive(&option, &value()?)?,
+            "-H" | "--height"  => options.height  = parse_positive(&option, &value()?)?,
+            "-s" | "--samples" => options.samples = parse_positive(&option, &value()?)?,
+            "-o" | "--output"  => options.outputs.push(value()?),
+            "-S" | "--scene"   => options.scene   = value()?,
+            "--preview"  => preview = Some(value()?),
+            "--seed"     => options.seed = Some(parse_number(&option, &value()?)?),
+            "--checkpoint" => checkpoint = Some(value()?),
+            "--checkpoint-interval" => {
+                checkpoint_interval = parse_number(&option, &value()?)?;
+
+                if !(checkpoint_interval >= 0.0 && checkpoint_interval.is_finite()) {
+                    return Err(format!("`{}` must be a non-negative number of seconds", option));
+                }
+            }
+            "--tonemap"  => tonemap = value()?,
+            "--accel"    => accel   = value()?,
+            "--preview-interval" => {
+                preview_interval = parse_number(&option, &value()?)?;
+
+                if !(preview_interval >= 0.0 && preview_interval.is_finite()) {
+                    return Err(format!("`{}` must be a non-negative number of seconds", option));
+                }
+            }
+            "--exposure" => {
+                options.display.exposure = parse_number(&option, &value()?)?;
+
+                if !options.display.exposure.is_finite() {
+                    return Err(format!("`{}` must be finite", option));
+                }
+            }
+            "--white" => {
+                white = parse_number(&option, &value()?)?;
+
+                if !(white > 0.0 && white.is_finite()) {
+                    return Err(format!("`{}` must be greater than zero", option));
+                }
+            }
+            "--eye"    => options.camera.eye    = Some(parse_vector(&option, &value()?)?),
+            "--target" => options.camera.target = Some(parse_vector(&option, &value()?)?),
+            "--up"     => options.camera.up     = Some(parse_vector(&option, &value()?)?),
+            "--fov"    => {
+                let fov: f32 = parse_number(&option, &value()?)?;
+
+                if !(fov > 0.0 && fov < 180.0) {
+                    return Err(format!("`{}` must be between 0 and 180 degrees", option));
+                }
+
+                options.camera.fov = Some(fov);
+            }
+            _ => return Err(format!("unknown option `{}`", option)),
+        }
+    }
+
+    options.display.tonemap = match tonemap.as_str() {
+        "clamp"             => Tonemap::Clamp,
+        "reinhard"          => Tonemap::Reinhard,
+        "extended-reinhard" => Tonemap::ExtendedReinhard(white),
+        "aces"              => Tonemap::Aces,
+        "agx"               => Tonemap::Agx,
+        _                   => return Err(format!("unknown tone mapping operator `{}`", tonemap)),
+    };
+
+    options.accel = match accel.as_str() {
+        "bvh2" => BvhWidth::Two,
+        "bvh4" => BvhWidth::Four,
+        "bvh8" => BvhWidth::Eight,
+        _      => return Err(format!("unknown acceleration structure `{}`", accel)),
+    };
+
+    if let Some(path) = preview {
+        if path.is_empty() {
+            return Err(String::from("preview path cannot be empty"));
+        }
+
+        options.preview = Some(PreviewOptions {
+            path,
+            interval: preview_interval,
+        });
+    }
+
+    match checkpoint {
+        Some(path) if path.is_empty() => {
+            return Err(String::from("checkpoint path cannot be empty"));
+        }
+        Some(path) => {
+            options.checkpoint = Some(CheckpointOptions {
+                path,
+                interval: checkpoint_interval,
+                resume,
+            });
+        }
+        None if resume => return Err(String::from("`--resume` requires `--checkpoint`")),
+        None => {}
+    }
+
+    if options.outputs.iter().any(|output| output.is_empty()) {
+        return Err(String::from("output path cannot be empty"));
+    }
+
+    if options.outputs.is_empty() {
+        options.outputs.push(String::from("output.png"));
+    }
+
+    if options.scene.is_empty() {
+        return Err(String::from("scene cannot be empty"));
+    }
+
+    Ok(Command::Render(Box::new(options)))
+}
@@ -0,0 +1,20 @@
+use super::{Environment, SharedEnvironment};
+use crate::Vec3;
+
+pub struct ConstantEnvironment {
+    color: Vec3,
+}
+
+impl ConstantEnvironment {
+    pub fn new(color: Vec3) -> SharedEnvironment {
+        super::make_shared(Self {
+            color,
+        })
+    }
+}
+
+impl Environment for ConstantEnvironment {
+    fn color(&self, _direction: Vec3) -> Vec3 {
+        self.color
+    }
+}
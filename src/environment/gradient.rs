@@ -0,0 +1,30 @@
+use super::{Environment, SharedEnvironment};
+use crate::Vec3;
+
+/// Vertical gradient going from `bottom` color for rays pointing straight down to `top` color
+/// for rays pointing straight up.
+pub struct GradientEnvironment {
+    bottom: Vec3,
+    top:    Vec3,
+}
+
+impl GradientEnvironment {
+    pub fn new(bottom: Vec3, top: Vec3) -> SharedEnvironment {
+        super::make_shared(Self {
+            bottom,
+            top,
+        })
+    }
+
+    pub fn sky() -> SharedEnvironment {
+        Self::new(Vec3::new(1.0, 1.0, 1.0), Vec3::new(0.5, 0.7, 1.0))
+    }
+}
+
+impl Environment for GradientEnvironment {
+    fn color(&self, direction: Vec3) -> Vec3 {
+        let t = 0.5 * (direction.extract().1 + 1.0);
+
+        self.bottom * (1.0 - t) + self.top * t
+    }
+}
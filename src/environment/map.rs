@@ -0,0 +1,120 @@
+use super::{Environment, SharedEnvironment};
+use crate::Vec3;
+
+use std::path::Path;
+use std::io::{self, BufReader};
+use std::fs::File;
+
+use image::{ImageError, ImageResult};
+use image::hdr::HdrDecoder;
+
+/// Equirectangular environment map. Radiance (.hdr) images are used as is, other image
+/// formats are assumed to be sRGB encoded and are converted to linear values.
+pub struct MapEnvironment {
+    pixels:    Vec<Vec3>,
+    width:     usize,
+    height:    usize,
+    rotation:  f32,
+    intensity: f32,
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.0;
+
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn read_pixels(path: &Path) -> ImageResult<(Vec<Vec3>, usize, usize)> {
+    let is_hdr = path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("hdr"));
+
+    if is_hdr {
+        let file    = File::open(path).map_err(ImageError::IoError)?;
+        let decoder = HdrDecoder::new(BufReader::new(file))?;
+
+        let metadata = decoder.metadata();
+        let pixels   = decoder.read_image_hdr()?
+            .into_iter()
+            .map(|pixel| Vec3::new(pixel.0[0], pixel.0[1], pixel.0[2]))
+            .collect();
+
+        Ok((pixels, metadata.width as usize, metadata.height as usize))
+    } else {
+        let image = image::open(path)?.into_rgb();
+
+        let (width, height) = image.dimensions();
+
+        let pixels = image.pixels()
+            .map(|pixel| {
+                Vec3::new(srgb_to_linear(pixel.0[0]), srgb_to_linear(pixel.0[1]),
+                          srgb_to_linear(pixel.0[2]))
+            })
+            .collect();
+
+        Ok((pixels, width as usize, height as usize))
+    }
+}
+
+impl MapEnvironment {
+    /// Loads environment map from `path`. The map is rotated around the Y axis by `rotation`
+    /// degrees and its radiance is scaled by `intensity`. Fails for images without pixels.
+    pub fn open(path: impl AsRef<Path>, rotation: f32,
+                intensity: f32) -> ImageResult<SharedEnvironment> {
+        let (pixels, width, height) = read_pixels(path.as_ref())?;
+
+        if width == 0 || height == 0 {
+            return Err(ImageError::IoError(io::Error::new(io::ErrorKind::InvalidData,
+                                                          "image is empty")));
+        }
+
+        Ok(super::make_shared(Self {
+            pixels,
+            width,
+            height,
+            rotation: rotation.to_radians(),
+            intensity,
+        }))
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> Vec3 {
+        self.pixels[y * self.width + x]
+    }
+}
+
+impl Environment for MapEnvironment {
+    fn color(&self, direction: Vec3) -> Vec3 {
+        let pi = std::f32::consts::PI;
+
+        let (x, y, z) = direction.normalized().extract();
+
+        let phi   = f32::atan2(z, x) + self.rotation;
+        let theta = y.clamp(-1.0, 1.0).acos();
+
+        let u = (phi / (2.0 * pi)).rem_euclid(1.0);
+        let v = theta / pi;
+
+        // Bilinear filtering, wrapping horizontally and clamping vertically.
+        let fx = u * self.width  as f32 - 0.5;
+        let fy = v * self.height as f32 - 0.5;
+
+        let x0 = fx.floor();
+        let y0 = fy.floor();
+        let tx = fx - x0;
+        let ty = fy - y0;
+
+        let wrap  = |x: f32| (x as isize).rem_euclid(self.width as isize) as usize;
+        let clamp = |y: f32| (y.max(0.0) as usize).min(self.height - 1);
+
+        let (x0, x1) = (wrap(x0), wrap(x0 + 1.0));
+        let (y0, y1) = (clamp(y0), clamp(y0 + 1.0));
+
+        let top    = self.pixel(x0, y0) * (1.0 - tx) + self.pixel(x1, y0) * tx;
+        let bottom = self.pixel(x0, y1) * (1.0 - tx) + self.pixel(x1, y1) * tx;
+
+        (top * (1.0 - ty) + bottom * ty) * self.intensity
+    }
+}
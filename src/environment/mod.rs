@@ -0,0 +1,26 @@
+//! Environments lighting the scene from infinity.
+
+mod constant;
+mod gradient;
+mod map;
+
+use std::sync::Arc;
+
+use crate::Vec3;
+
+pub use constant::ConstantEnvironment;
+pub use gradient::GradientEnvironment;
+pub use map::MapEnvironment;
+
+/// Environment which can be shared between render threads.
+pub type SharedEnvironment = Arc<dyn Environment + Send + Sync>;
+
+/// Radiance arriving from infinitely far away, seen by rays which don't hit any object.
+pub trait Environment {
+    /// Returns radiance arriving from `direction`.
+    fn color(&self, direction: Vec3) -> Vec3;
+}
+
+fn make_shared(environment: impl Environment + Send + Sync + 'static) -> SharedEnvironment {
+    Arc::new(environment)
+}
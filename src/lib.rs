@@ -0,0 +1,41 @@
+//! Multithreaded path tracer.
+//!
+//! A render starts with a [`Scene`](scene::Scene) which is filled with
+//! [traceable] objects using [materials](material), either by hand, by one of the
+//! [generators](scene::generators) or by [loading](scene::loader) a scene file. The
+//! [`Raytracer`](raytracer::Raytracer) then renders the scene seen by a
+//! [`Camera`](math::Camera) into a buffer of linear radiance which can be written to disk by
+//! the [`output`] module.
+//!
+//! ```no_run
+//! use path_tracer::math::CameraSettings;
+//! use path_tracer::output::{self, DisplayTransform};
+//! use path_tracer::raytracer::Raytracer;
+//! use path_tracer::rng::Rng;
+//! use path_tracer::scene::{Scene, generators};
+//!
+//! let mut scene = Scene::new();
+//! let settings  = generators::cornell_box(&mut scene, &mut Rng::with_seed(1));
+//!
+//! let raytracer = Raytracer::new(settings.camera(320, 240), scene, 4, 1);
+//! let image     = raytracer.render_image();
+//!
+//! output::save("cornell.png", 320, 240, &image, &DisplayTransform::default()).unwrap();
+//! ```
+
+#![allow(clippy::new_ret_no_self, clippy::upper_case_acronyms)]
+
+pub mod parallel_renderer;
+pub mod raytracer;
+pub mod traceable;
+pub mod material;
+pub mod texture;
+pub mod environment;
+pub mod scene;
+pub mod math;
+pub mod rng;
+pub mod output;
+pub mod checkpoint;
+pub mod spectrum;
+
+pub use math::{Vec3, Ray};
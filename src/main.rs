@@ -1,47 +1,31 @@
-#![allow(dead_code)]
-
-mod parallel_renderer;
-mod raytracer;
-mod traceable;
-mod material;
-mod texture;
-mod scene;
-mod math;
-mod rng;
-
-pub use math::{Vec3, Ray};
-
-use scene::Scene;
-use math::Camera;
-use parallel_renderer::ParallelRenderer;
-use raytracer::{Raytracer, Statistics, Pixel};
-
-use std::sync::atomic::Ordering;
-use std::time::Duration;
+mod cli;
+
+use path_tracer::Vec3;
+use path_tracer::scene::{self, Scene, BvhSettings};
+use path_tracer::scene::loader::LoadError;
+use path_tracer::math::CameraSettings;
+use path_tracer::parallel_renderer::ParallelRenderer;
+use path_tracer::raytracer::{Raytracer, Statistics, Pixel};
+use path_tracer::checkpoint::{self, Checkpoint};
+use path_tracer::output;
+use path_tracer::rng::Rng;
+use cli::{Command, Options};
+
+use std::time::{Duration, Instant};
 use std::io::{self, Write};
-use std::sync::Arc;
+use std::convert::TryFrom;
+use std::path::Path;
+use std::process;
+use std::fs;
 use std::thread;
 
-use image::RgbImage;
+fn reporter(stats: &Statistics, pixel_count: usize, passes: usize) {
+    let total = pixel_count * passes;
 
-fn flatten_image(mut buffer: Vec<[u8; 3]>) -> Vec<u8> {
-    let buf = buffer.as_mut_ptr();
-    let len = buffer.len();
-    let cap = buffer.capacity();
-
-    assert!(std::mem::align_of::<u8>() == std::mem::align_of::<[u8; 3]>());
-
-    std::mem::forget(buffer);
-
-    unsafe {
-        Vec::from_raw_parts(buf as *mut u8, len * 3, cap * 3)
-    }
-}
-
-fn reporter(stats: &Statistics, pixel_count: usize) {
     loop {
-        let pixels_done = stats.pixels_done.load(Ordering::Relaxed);
-        let progress    = pixels_done as f64 / pixel_count as f64;
+        let pixels_done = stats.pixels_done();
+        let progress    = pixels_done as f64 / total as f64;
+        let pass        = (pixels_done / pixel_count + 1).min(passes);
 
         let elapsed = stats.elapsed();
 
@@ -58,9 +42,10 @@ fn reporter(stats: &Statistics, pixel_count: usize) {
             }
         }
 
-        print!("] {:.1}% | {:.3}s elapsed", progress * 100.0, elapsed);
+        print!("] {:.1}% | pass {}/{} | {:.3}s elapsed", progress * 100.0, pass, passes,
+               elapsed);
 
-        if pixels_done == pixel_count {
+        if pixels_done == total {
             println!();
             break;
         }
@@ -70,53 +55,254 @@ fn reporter(stats: &Statistics, pixel_count: usize) {
     }
 }
 
-fn main() {
-    assert!(is_x86_feature_detected!("avx2"), "This CPU doesn't support AVX2 which is required.");
+/// Loads the scene and its camera. Also returns the scene sources, which are the scene file
+/// followed by all files it references, or the name of a built-in scene.
+fn load_scene(options: &Options, seed: u64)
+    -> Result<(Scene, CameraSettings, Vec<Vec<u8>>), String>
+{
+    // Random stream reserved for scene generation, pixels use the others.
+    const SCENE_STREAM: u64 = u64::MAX;
+
+    type Generator = fn(&mut Scene, &mut Rng) -> CameraSettings;
+
+    let generator = match options.scene.as_str() {
+        "simple"  => Some(scene::generators::simple_scene as Generator),
+        "random"  => Some(scene::generators::random_scene as Generator),
+        "cornell" => Some(scene::generators::cornell_box as Generator),
+        _         => None,
+    };
+
+    let bvh_settings = BvhSettings {
+        width: options.accel,
+        ..BvhSettings::default()
+    };
+
+    if let Some(generator) = generator {
+        let mut scene = Scene::new();
+
+        scene.set_bvh_settings(bvh_settings);
+
+        let camera = generator(&mut scene, &mut Rng::with_key(seed, SCENE_STREAM));
+
+        return Ok((scene, camera, vec![options.scene.clone().into_bytes()]));
+    }
+
+    let path   = Path::new(&options.scene);
+    let source = fs::read_to_string(path)
+        .map_err(|err| {
+            if options.scene.contains(['.', '/']) {
+                LoadError::Io(path.to_path_buf(), err).to_string()
+            } else {
+                format!("`{}` is neither a built-in scene ({}) nor a scene file",
+                        options.scene, cli::SCENES.join(", "))
+            }
+        })?;
+
+    let loaded = scene::loader::parse_with_settings(&source, path, bvh_settings)
+        .map_err(|err| err.to_string())?;
+
+    let mut sources = vec![source.into_bytes()];
+
+    for file in &loaded.files {
+        let contents = fs::read(file)
+            .map_err(|err| LoadError::Io(file.clone(), err).to_string())?;
+
+        sources.push(contents);
+    }
+
+    Ok((loaded.scene, loaded.camera, sources))
+}
+
+/// Applies camera overrides from `options` to the scene camera `settings`.
+fn camera(options: &Options, settings: CameraSettings) -> Result<CameraSettings, String> {
+    let settings = CameraSettings {
+        eye:    options.camera.eye.unwrap_or(settings.eye),
+        target: options.camera.target.unwrap_or(settings.target),
+        up:     options.camera.up.unwrap_or(settings.up),
+        fov:    options.camera.fov.unwrap_or(settings.fov),
+    };
+
+    let forward = settings.target - settings.eye;
+
+    if forward.length_sqr() == 0.0 {
+        return Err(String::from("camera eye and target cannot be the same point"));
+    }
+
+    if Vec3::cross(forward, settings.up).length_sqr() == 0.0 {
+        return Err(String::from("camera up vector cannot be zero or parallel to the view \
+                                 direction"));
+    }
+
+    Ok(settings)
+}
+
+/// Loads the checkpoint to resume and verifies that it belongs to the render in `options`.
+/// The scene is checked separately once it's loaded.
+fn load_checkpoint(options: &Options, path: &str) -> Result<(Checkpoint, Vec<Pixel>), String> {
+    let (checkpoint, buffer) = Checkpoint::load(path)
+        .map_err(|err| format!("failed to load checkpoint `{}`: {}", path, err))?;
+
+    if (checkpoint.width, checkpoint.height) != (options.width, options.height) {
+        return Err(format!("checkpoint `{}` is for a {}x{} image", path, checkpoint.width,
+                           checkpoint.height));
+    }
+
+    if checkpoint.samples != options.samples {
+        return Err(format!("checkpoint `{}` is for a render with {} samples", path,
+                           checkpoint.samples));
+    }
+
+    if options.seed.is_some_and(|seed| seed != checkpoint.seed) {
+        return Err(format!("checkpoint `{}` was rendered with seed {}", path, checkpoint.seed));
+    }
 
-    let width   = 3840 - 200;
-    let height  = 2160 - 200;
-    let samples = 16;
+    if checkpoint.spectral != options.spectral {
+        let mode = if checkpoint.spectral { "spectrally" } else { "in RGB" };
 
-    let camera = Camera::new(
-        Vec3::new(12.0, 2.0, 3.0),
-        Vec3::new(0.0, 0.0, 0.0),
-        Vec3::new(0.0, 1.0, 0.0),
-        20.0,
-        width,
-        height,
-    );
+        return Err(format!("checkpoint `{}` was rendered {}", path, mode));
+    }
+
+    Ok((checkpoint, buffer))
+}
+
+fn render(options: Options) -> Result<(), String> {
+    let (width, height) = (options.width, options.height);
+
+    if u32::try_from(width).is_err() || u32::try_from(height).is_err() {
+        return Err(String::from("image dimensions are too large"));
+    }
+
+    let resumed = match &options.checkpoint {
+        Some(checkpoint) if checkpoint.resume => {
+            Some(load_checkpoint(&options, &checkpoint.path)?)
+        }
+        _ => None,
+    };
+
+    let seed = match &resumed {
+        Some((checkpoint, _)) => checkpoint.seed,
+        None                  => options.seed.unwrap_or_else(|| Rng::new().rand()),
+    };
 
-    let mut scene = Scene::new();
+    let (scene, settings, sources) = load_scene(&options, seed)?;
 
-    scene::generators::simple_scene(&mut scene);
-    //scene::generators::random_scene(&mut scene);
+    let settings = camera(&options, settings)?;
+    let inputs   = checkpoint::hash_inputs(&sources, &settings);
+
+    if let (Some((checkpoint, _)), Some(options)) = (&resumed, &options.checkpoint) {
+        if checkpoint.inputs != inputs {
+            return Err(format!("checkpoint `{}` was rendered with a different scene, camera or \
+                                build features", options.path));
+        }
+    }
+
+    let camera = settings.camera(width, height);
+
+    print!("Constructing BVH for {} objects... ", scene.object_count());
+
+    io::stdout().flush().unwrap();
+
+    let start_time    = Instant::now();
+    let mut raytracer = Raytracer::new(camera, scene, options.samples, seed);
+
+    raytracer.set_spectral(options.spectral);
+
+    println!("done in {:.3}s.", start_time.elapsed().as_secs_f64());
 
-    let raytracer   = Raytracer::new(camera, scene, samples);
     let pixel_count = raytracer.pixel_count();
+    let passes      = raytracer.passes();
+
+    let (first_pass, mut buffer) = match resumed {
+        Some((checkpoint, buffer)) => (checkpoint.passes_done, buffer),
+        None                       => (0, vec![Pixel::default(); pixel_count]),
+    };
 
     let mut renderer = ParallelRenderer::new();
-    let mut buffer   = vec![Pixel::default(); pixel_count];
 
-    let stats = Arc::new(Statistics::new());
+    let mut last_preview    = Instant::now();
+    let mut last_checkpoint = Instant::now();
+    let mut warnings        = Vec::new();
+
+    let pass_done = |passes_done, buffer: &[Pixel]| {
+        // Failing to save intermediate results shouldn't throw away the render, so errors
+        // are only reported at the end.
+        if let Some(checkpoint) = &options.checkpoint {
+            let due = last_checkpoint.elapsed().as_secs_f64() >= checkpoint.interval;
+
+            if due || passes_done == passes {
+                let state = Checkpoint {
+                    width,
+                    height,
+                    samples: options.samples,
+                    passes_done,
+                    seed,
+                    spectral: options.spectral,
+                    inputs,
+                };
+
+                if let Err(err) = state.save(&checkpoint.path, buffer) {
+                    warnings.push(format!("failed to save checkpoint `{}`: {}",
+                                          checkpoint.path, err));
+                }
+
+                last_checkpoint = Instant::now();
+            }
+        }
 
-    let reporter = {
-        let stats = stats.clone();
+        if let Some(preview) = &options.preview {
+            let due = last_preview.elapsed().as_secs_f64() >= preview.interval;
 
-        thread::spawn(move || reporter(&stats, pixel_count))
+            if due && passes_done < passes {
+                if let Err(err) = output::save(&preview.path, width, height, buffer,
+                                               &options.display) {
+                    warnings.push(format!("failed to save preview image `{}`: {}",
+                                          preview.path, err));
+                }
+
+                last_preview = Instant::now();
+            }
+        }
     };
 
-    let context = (raytracer, stats);
+    thread::scope(|scope| {
+        let stats    = raytracer.statistics();
+        let reporter = scope.spawn(move || reporter(stats, pixel_count, passes));
 
-    renderer.render(&context, &mut buffer, move |context, rng, start_pixel, pixels| {
-        let (raytracer, stats) = context;
+        raytracer.render(&mut renderer, &mut buffer, first_pass..passes, pass_done);
 
-        raytracer.render_fragment(start_pixel, pixels, &stats, rng);
+        reporter.join().unwrap();
     });
 
-    reporter.join().unwrap();
+    for warning in warnings {
+        eprintln!("warning: {}", warning);
+    }
+
+    for path in &options.outputs {
+        output::save(path, width, height, &buffer, &options.display)
+            .map_err(|err| format!("failed to save output image `{}`: {}", path, err))?;
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let result = match cli::parse(std::env::args().skip(1)) {
+        Ok(Command::Help)            => {
+            println!("{}", cli::usage());
+
+            Ok(())
+        }
+        Ok(Command::Render(options)) => render(*options),
+        Err(err)                     => {
+            eprintln!("error: {}\n\nFor more information try `--help`.", err);
 
-    RgbImage::from_raw(width as u32, height as u32, flatten_image(buffer))
-        .expect("Failed to create image buffer for the PNG.")
-        .save("output.png")
-        .expect("Failed to save output image.");
+            process::exit(2);
+        }
+    };
+
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+
+        process::exit(1);
+    }
 }
@@ -2,39 +2,121 @@ use super::{Material, SharedMaterial};
 use crate::{Vec3, Ray};
 use crate::traceable::HitRecord;
 use crate::rng::Rng;
+use crate::spectrum;
 use crate::math;
 
+/// Index of refraction, which can vary with wavelength.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Ior {
+    Constant(f32),
+    /// Cauchy's equation `n = A + B / λ² + C / λ⁴` with λ in micrometres.
+    Cauchy([f32; 3]),
+    /// Sellmeier equation `n² = 1 + Σ B_i λ² / (λ² - C_i)` with λ in micrometres, given by
+    /// the `B` and `C` coefficients.
+    Sellmeier([f32; 3], [f32; 3]),
+}
+
+impl Ior {
+    /// Wavelength in nanometres at which the index is evaluated when rendering in RGB. It's the
+    /// helium d-line at which indices of glasses are usually quoted.
+    pub const REFERENCE_WAVELENGTH: f32 = 587.56;
+
+    /// Index of refraction at `wavelength` in nanometres.
+    pub fn at(self, wavelength: f32) -> f32 {
+        let l2 = (wavelength * 1e-3) * (wavelength * 1e-3);
+
+        match self {
+            Ior::Constant(n)       => n,
+            Ior::Cauchy([a, b, c]) => a + b / l2 + c / (l2 * l2),
+            Ior::Sellmeier(b, c) => {
+                let sum: f32 = b.iter().zip(&c).map(|(b, c)| b * l2 / (l2 - c)).sum();
+
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+
+    pub fn is_dispersive(self) -> bool {
+        !matches!(self, Ior::Constant(..))
+    }
+
+    /// Returns true if the index is finite and positive over the whole sampled spectrum.
+    pub fn is_valid(self) -> bool {
+        let (min, max) = (spectrum::MIN_WAVELENGTH as usize, spectrum::MAX_WAVELENGTH as usize);
+
+        (min..=max).map(|wavelength| self.at(wavelength as f32))
+            .chain(std::iter::once(self.at(Self::REFERENCE_WAVELENGTH)))
+            .all(|n| n > 0.0 && n.is_finite())
+    }
+}
+
+impl From<f32> for Ior {
+    fn from(n: f32) -> Self {
+        Ior::Constant(n)
+    }
+}
+
 pub struct Dielectric {
-    ref_idx: f32
+    ior:        Ior,
+    absorption: Option<Vec3>,
 }
 
 impl Dielectric {
-    pub fn new(ref_idx: f32) -> SharedMaterial {
+    pub fn new(ior: impl Into<Ior>) -> SharedMaterial {
+        let ior = ior.into();
+
+        assert!(ior.is_valid(), "Index of refraction must be positive and finite.");
+
+        super::make_shared(Self {
+            ior,
+            absorption: None,
+        })
+    }
+
+    /// Creates tinted dielectric. `color` is the fraction of light passing through unit
+    /// distance of the medium when `density` is one, higher densities absorb more. Density
+    /// must be positive, black channels absorb all light of their color.
+    pub fn with_absorption(ior: impl Into<Ior>, color: Vec3, density: f32) -> SharedMaterial {
+        let ior       = ior.into();
+        let (r, g, b) = color.extract();
+
+        assert!(ior.is_valid(), "Index of refraction must be positive and finite.");
+        assert!([r, g, b].iter().all(|c| (0.0..=1.0).contains(c)),
+                "Absorption color must be in the [0, 1] range.");
+        // Zero density would make black channels infinity times zero.
+        assert!(density > 0.0, "Absorption density must be positive.");
+
+        // Beer-Lambert law: transmittance over distance `d` is `exp(-absorption * d)`.
+        let absorption = Vec3::new(-r.ln(), -g.ln(), -b.ln()) * density;
+
         super::make_shared(Self {
-            ref_idx,
+            ior,
+            absorption: Some(absorption),
         })
     }
 }
 
 impl Material for Dielectric {
     fn scatter(&self, ray: &Ray, record: &HitRecord, rng: &mut Rng) -> Option<(Vec3, Ray)> {
+        let ref_idx = self.ior.at(ray.wavelength.unwrap_or(Ior::REFERENCE_WAVELENGTH));
+
         let dir = ray.direction;
         let dot = Vec3::dot(dir, record.normal);
 
         let (outward_normal, ni_over_nt, cosine) = if dot > 0.0 {
-            let cosine = self.ref_idx * dot;
+            let cosine = ref_idx * dot;
 
-            (-record.normal, self.ref_idx, cosine)
+            (-record.normal, ref_idx, cosine)
         } else {
             let cosine = -dot;
 
-            (record.normal, 1.0 / self.ref_idx, cosine)
+            (record.normal, 1.0 / ref_idx, cosine)
         };
-        
+
         let reflected = math::reflect(dir, record.normal);
 
         if let Some(refracted) = math::refract(dir, outward_normal, ni_over_nt) {
-            let reflect_prob = math::schlick(cosine, self.ref_idx);
+            let reflect_prob = math::schlick(cosine, ref_idx);
             let rand: f32    = rng.rand();
 
             let new_dir = if rand < reflect_prob {
@@ -48,4 +130,12 @@ impl Material for Dielectric {
             Some((Vec3::fill(1.0), Ray::new(record.point, reflected)))
         }
     }
+
+    fn absorption(&self) -> Option<Vec3> {
+        self.absorption
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.ior.is_dispersive()
+    }
 }
@@ -0,0 +1,30 @@
+use super::{Material, SharedMaterial};
+use crate::{Vec3, Ray};
+use crate::traceable::HitRecord;
+use crate::rng::Rng;
+
+pub struct DiffuseLight {
+    emission: Vec3,
+}
+
+impl DiffuseLight {
+    pub fn new(emission: Vec3) -> SharedMaterial {
+        super::make_shared(Self {
+            emission,
+        })
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _ray: &Ray, _record: &HitRecord, _rng: &mut Rng) -> Option<(Vec3, Ray)> {
+        None
+    }
+
+    fn emitted(&self, _ray: &Ray, _record: &HitRecord) -> Vec3 {
+        self.emission
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+}
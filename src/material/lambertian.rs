@@ -28,19 +28,47 @@ impl Lambertian {
     }
 }
 
-impl Material for Lambertian {
-    fn scatter(&self, _ray: &Ray, record: &HitRecord, rng: &mut Rng) -> Option<(Vec3, Ray)> {
-        let target = record.point + record.normal + math::random_in_unit_sphere(rng);
-
-        let color = match &self.albedo {
+impl Lambertian {
+    fn color(&self, record: &HitRecord) -> Vec3 {
+        match &self.albedo {
             Albedo::Texture(albedo) => {
                 let (u, v) = record.uv();
 
                 albedo.color(u, v, record.point)
             }
             Albedo::Solid(albedo)   => *albedo,
-        };
+        }
+    }
+
+    fn normal(ray: &Ray, record: &HitRecord) -> Vec3 {
+        // Surfaces like triangles can be hit from both sides, always scatter back towards
+        // the side the ray came from.
+        if Vec3::dot(ray.direction, record.normal) > 0.0 {
+            -record.normal
+        } else {
+            record.normal
+        }
+    }
+}
+
+impl Material for Lambertian {
+    fn scatter(&self, ray: &Ray, record: &HitRecord, rng: &mut Rng) -> Option<(Vec3, Ray)> {
+        let normal = Self::normal(ray, record);
+        let target = record.point + normal + math::random_unit_vector(rng);
+
+        Some((self.color(record), Ray::new(record.point, target - record.point)))
+    }
+
+    fn evaluate(&self, ray: &Ray, record: &HitRecord, direction: Vec3) -> Option<(Vec3, f32)> {
+        let cosine = Vec3::dot(Self::normal(ray, record), direction);
+
+        if cosine <= 0.0 {
+            return Some((Vec3::zero(), 0.0));
+        }
+
+        // Scattered directions are cosine distributed, so the PDF matches the BSDF.
+        let pdf = cosine * std::f32::consts::FRAC_1_PI;
 
-        Some((color, Ray::new(record.point, target - record.point)))
+        Some((self.color(record) * pdf, pdf))
     }
 }
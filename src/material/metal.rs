@@ -23,7 +23,7 @@ impl Material for Metal {
         let reflected = math::reflect(ray.direction, record.normal);
 
         if Vec3::dot(reflected, record.normal) > 0.0 {
-            let fuzz = math::random_in_unit_sphere(rng) * self.fuziness;
+            let fuzz = math::random_unit_vector(rng) * self.fuziness;
 
             Some((self.albedo, Ray::new(record.point, reflected + fuzz)))
         } else {
@@ -0,0 +1,187 @@
+//! Microfacet model shared by rough materials. Directions are expressed in a local shading
+//! frame in which the surface normal is the Z axis.
+
+use crate::Vec3;
+use crate::math;
+
+use std::f32::consts::PI;
+
+/// Smaller roughness makes the distribution too peaked to be represented by `f32`.
+const MIN_ALPHA: f32 = 1e-3;
+
+/// Orthonormal frame around a shading normal.
+pub struct Frame {
+    s: Vec3,
+    t: Vec3,
+    n: Vec3,
+}
+
+impl Frame {
+    pub fn new(normal: Vec3) -> Self {
+        let (s, t) = math::orthonormal_basis(normal);
+
+        Self {
+            s,
+            t,
+            n: normal,
+        }
+    }
+
+    pub fn to_local(&self, v: Vec3) -> Vec3 {
+        Vec3::new(Vec3::dot(v, self.s), Vec3::dot(v, self.t), Vec3::dot(v, self.n))
+    }
+
+    pub fn to_world(&self, v: Vec3) -> Vec3 {
+        let (x, y, z) = v.extract();
+
+        self.s * x + self.t * y + self.n * z
+    }
+}
+
+fn cos_theta(v: Vec3) -> f32 {
+    v.extract().2
+}
+
+/// Isotropic GGX (Trowbridge-Reitz) distribution of microfacet normals with Smith
+/// masking-shadowing.
+#[derive(Copy, Clone)]
+pub struct Ggx {
+    alpha: f32,
+}
+
+impl Ggx {
+    /// Creates distribution for perceptual `roughness` in the [0, 1] range, which is
+    /// squared to get the GGX width parameter.
+    pub fn new(roughness: f32) -> Self {
+        Self {
+            alpha: (roughness * roughness).max(MIN_ALPHA),
+        }
+    }
+
+    /// Density of microfacets with normal `m` per unit of the macrosurface area.
+    pub fn distribution(&self, m: Vec3) -> f32 {
+        let cos = cos_theta(m);
+
+        if cos <= 0.0 {
+            return 0.0;
+        }
+
+        let alpha2 = self.alpha * self.alpha;
+        let cos2   = cos * cos;
+        let denom  = cos2 * (alpha2 - 1.0) + 1.0;
+
+        alpha2 / (PI * denom * denom)
+    }
+
+    fn lambda(&self, v: Vec3) -> f32 {
+        let cos2 = cos_theta(v) * cos_theta(v);
+        let tan2 = (1.0 - cos2).max(0.0) / cos2;
+
+        ((1.0 + self.alpha * self.alpha * tan2).sqrt() - 1.0) * 0.5
+    }
+
+    /// Fraction of microfacets visible from direction `v`.
+    pub fn masking(&self, v: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(v))
+    }
+
+    /// Fraction of microfacets visible from both `wo` and `wi` (height-correlated form).
+    pub fn masking_shadowing(&self, wo: Vec3, wi: Vec3) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Samples microfacet normal visible from `wo` using uniform random numbers `u1` and
+    /// `u2`. "Sampling the GGX Distribution of Visible Normals" by Heitz. Returns `None` when
+    /// rounding gives a normal perpendicular to the surface, which has zero density.
+    pub fn sample_visible(&self, wo: Vec3, u1: f32, u2: f32) -> Option<Vec3> {
+        let (x, y, z) = wo.extract();
+
+        // Stretch the view direction so that the distribution becomes a hemisphere.
+        let vh = Vec3::new(self.alpha * x, self.alpha * y, z).normalized();
+
+        let (vx, vy, vz) = vh.extract();
+        let length2      = vx * vx + vy * vy;
+
+        let t1 = if length2 > 0.0 {
+            Vec3::new(-vy, vx, 0.0) / length2.sqrt()
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+
+        let t2 = Vec3::cross(vh, t1);
+
+        // Uniform point on a disc, warped towards the visible part of the hemisphere.
+        let r          = u1.sqrt();
+        let (sin, cos) = (2.0 * PI * u2).sin_cos();
+        let p1         = r * cos;
+        let s          = 0.5 * (1.0 + vz);
+        let p2         = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * sin;
+        let p3         = (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+
+        let (nx, ny, nz) = (t1 * p1 + t2 * p2 + vh * p3).extract();
+
+        if nz <= 0.0 {
+            return None;
+        }
+
+        Some(Vec3::new(self.alpha * nx, self.alpha * ny, nz).normalized())
+    }
+
+    /// PDF of `sample_visible` picking normal `m` when viewed from `wo`.
+    pub fn visible_pdf(&self, wo: Vec3, m: Vec3) -> f32 {
+        let cos = Vec3::dot(wo, m);
+
+        if cos <= 0.0 {
+            return 0.0;
+        }
+
+        self.masking(wo) * cos * self.distribution(m) / cos_theta(wo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Integrates `visible_pdf` over the hemisphere with the midpoint rule in cosine of the
+    /// polar angle and in azimuth.
+    fn integrate_visible_pdf(ggx: Ggx, wo: Vec3) -> f32 {
+        const COS_STEPS: usize = 4000;
+        const PHI_STEPS: usize = 64;
+
+        let d_cos = 1.0 / COS_STEPS as f32;
+        let d_phi = 2.0 * PI / PHI_STEPS as f32;
+
+        let mut sum = 0.0;
+
+        for i in 0..COS_STEPS {
+            let cos = (i as f32 + 0.5) * d_cos;
+            let sin = (1.0 - cos * cos).sqrt();
+
+            for j in 0..PHI_STEPS {
+                let (sin_phi, cos_phi) = ((j as f32 + 0.5) * d_phi).sin_cos();
+                let m                  = Vec3::new(sin * cos_phi, sin * sin_phi, cos);
+
+                sum += ggx.visible_pdf(wo, m) * d_cos * d_phi;
+            }
+        }
+
+        sum
+    }
+
+    #[test]
+    fn visible_pdf_integrates_to_one() {
+        for &roughness in &[0.3, 0.6, 1.0] {
+            let ggx = Ggx::new(roughness);
+
+            for &angle in &[0.0f32, 45.0, 80.0] {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let integral   = integrate_visible_pdf(ggx, Vec3::new(sin, 0.0, cos));
+
+                assert!((integral - 1.0).abs() < 1e-2,
+                        "Visible normals PDF for roughness {} at {} degrees integrates to {}.",
+                        roughness, angle, integral);
+            }
+        }
+    }
+}
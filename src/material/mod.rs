@@ -1,6 +1,12 @@
+//! Materials describing how light scatters off surfaces.
+
 mod lambertian;
 mod dielectric;
 mod metal;
+mod diffuse_light;
+mod rough_conductor;
+mod rough_dielectric;
+mod microfacet;
 
 use std::sync::Arc;
 
@@ -9,13 +15,49 @@ use crate::traceable::HitRecord;
 use crate::rng::Rng;
 
 pub use lambertian::Lambertian;
-pub use dielectric::Dielectric;
+pub use dielectric::{Dielectric, Ior};
 pub use metal::Metal;
+pub use diffuse_light::DiffuseLight;
+pub use rough_conductor::{RoughConductor, Conductor};
+pub use rough_dielectric::RoughDielectric;
 
+/// Material which can be shared between objects and render threads.
 pub type SharedMaterial = Arc<dyn Material + Send + Sync>;
 
 pub trait Material {
+    /// Picks direction `ray` scatters into at `record`. Returns attenuation of the radiance
+    /// coming from the scattered ray, or `None` if the ray gets absorbed.
     fn scatter(&self, ray: &Ray, record: &HitRecord, rng: &mut Rng) -> Option<(Vec3, Ray)>;
+
+    /// Radiance emitted by the surface towards the origin of `ray`.
+    fn emitted(&self, _ray: &Ray, _record: &HitRecord) -> Vec3 {
+        Vec3::zero()
+    }
+
+    /// Returns true if `emitted` can be non-zero.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+
+    /// Evaluates scattering of `ray` into `direction`. Returns the BSDF value multiplied by
+    /// the cosine term and the solid angle PDF of `scatter` picking `direction`.
+    ///
+    /// Materials which scatter only into discrete directions (like perfect mirrors) return
+    /// `None` as they can't be combined with light sampling.
+    fn evaluate(&self, _ray: &Ray, _record: &HitRecord, _direction: Vec3) -> Option<(Vec3, f32)> {
+        None
+    }
+
+    /// Absorption coefficient of the medium enclosed by the surface, per unit of distance.
+    /// Radiance of rays travelling inside decays exponentially with the travelled distance.
+    fn absorption(&self) -> Option<Vec3> {
+        None
+    }
+
+    /// Returns true if scattering depends on the wavelength carried by the ray.
+    fn is_dispersive(&self) -> bool {
+        false
+    }
 }
 
 fn make_shared(material: impl Material + Send + Sync + 'static) -> SharedMaterial {
@@ -0,0 +1,126 @@
+use super::{Material, SharedMaterial};
+use super::microfacet::{Frame, Ggx};
+use crate::{Vec3, Ray};
+use crate::traceable::HitRecord;
+use crate::rng::Rng;
+use crate::math;
+
+/// Metals with measured complex indices of refraction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Conductor {
+    Gold,
+    Copper,
+    Aluminium,
+    Silver,
+}
+
+impl Conductor {
+    /// Real and imaginary parts of the index of refraction at 650, 550 and 450 nm.
+    pub fn ior(self) -> (Vec3, Vec3) {
+        match self {
+            Conductor::Gold => {
+                (Vec3::new(0.143, 0.374, 1.442), Vec3::new(3.983, 2.385, 1.603))
+            }
+            Conductor::Copper => {
+                (Vec3::new(0.200, 0.924, 1.102), Vec3::new(3.912, 2.452, 2.142))
+            }
+            Conductor::Aluminium => {
+                (Vec3::new(1.657, 0.880, 0.521), Vec3::new(9.224, 6.270, 4.837))
+            }
+            Conductor::Silver => {
+                (Vec3::new(0.155, 0.117, 0.138), Vec3::new(4.828, 3.122, 2.147))
+            }
+        }
+    }
+}
+
+/// Rough metal surface described by the GGX microfacet distribution. Reflectance follows
+/// from the Fresnel equations of its complex index of refraction.
+pub struct RoughConductor {
+    eta:          Vec3,
+    k:            Vec3,
+    distribution: Ggx,
+}
+
+impl RoughConductor {
+    /// Creates conductor with index of refraction `eta + ik`. `roughness` is in the [0, 1]
+    /// range, zero being a nearly perfect mirror.
+    pub fn new(eta: Vec3, k: Vec3, roughness: f32) -> SharedMaterial {
+        assert!((0.0..=1.0).contains(&roughness), "Roughness must be in the [0, 1] range.");
+
+        super::make_shared(Self {
+            eta,
+            k,
+            distribution: Ggx::new(roughness),
+        })
+    }
+
+    pub fn preset(conductor: Conductor, roughness: f32) -> SharedMaterial {
+        let (eta, k) = conductor.ior();
+
+        Self::new(eta, k, roughness)
+    }
+
+    /// Shading frame on the side of the surface `ray` came from, together with the local
+    /// direction towards the ray origin.
+    fn frame(ray: &Ray, record: &HitRecord) -> (Frame, Vec3) {
+        let normal = if Vec3::dot(ray.direction, record.normal) > 0.0 {
+            -record.normal
+        } else {
+            record.normal
+        };
+
+        let frame = Frame::new(normal);
+        let wo    = frame.to_local(-ray.direction);
+
+        (frame, wo)
+    }
+}
+
+impl Material for RoughConductor {
+    fn scatter(&self, ray: &Ray, record: &HitRecord, rng: &mut Rng) -> Option<(Vec3, Ray)> {
+        let (frame, wo) = Self::frame(ray, record);
+
+        if wo.extract().2 <= 0.0 {
+            return None;
+        }
+
+        let m  = self.distribution.sample_visible(wo, rng.rand(), rng.rand())?;
+        let wi = math::reflect(-wo, m);
+
+        // Reflections off microfacets can point below the macrosurface, such paths end.
+        if wi.extract().2 <= 0.0 {
+            return None;
+        }
+
+        // BSDF times cosine divided by the PDF of the sampled direction.
+        let fresnel = math::fresnel_conductor(Vec3::dot(wo, m), self.eta, self.k);
+        let weight  = self.distribution.masking_shadowing(wo, wi) /
+            self.distribution.masking(wo);
+
+        Some((fresnel * weight, Ray::new(record.point, frame.to_world(wi))))
+    }
+
+    fn evaluate(&self, ray: &Ray, record: &HitRecord, direction: Vec3) -> Option<(Vec3, f32)> {
+        let (frame, wo) = Self::frame(ray, record);
+        let wi          = frame.to_local(direction);
+
+        let (cos_o, cos_i) = (wo.extract().2, wi.extract().2);
+
+        if cos_o <= 0.0 || cos_i <= 0.0 {
+            return Some((Vec3::zero(), 0.0));
+        }
+
+        let m = (wo + wi).normalized();
+        let d = self.distribution.distribution(m);
+
+        let fresnel = math::fresnel_conductor(Vec3::dot(wo, m), self.eta, self.k);
+        let bsdf    = fresnel * (d * self.distribution.masking_shadowing(wo, wi) /
+                                 (4.0 * cos_o));
+
+        // Visible normal PDF converted to the PDF of the reflected direction.
+        let pdf = self.distribution.visible_pdf(wo, m) / (4.0 * Vec3::dot(wo, m));
+
+        Some((bsdf, pdf))
+    }
+}
@@ -0,0 +1,189 @@
+use super::{Material, SharedMaterial};
+use super::microfacet::{Frame, Ggx};
+use crate::{Vec3, Ray};
+use crate::traceable::HitRecord;
+use crate::texture::SharedTexture;
+use crate::rng::Rng;
+use crate::math;
+
+enum Roughness {
+    Solid(f32),
+    Texture(SharedTexture),
+}
+
+/// Rough interface between vacuum and a dielectric, like frosted glass. Both reflection and
+/// refraction happen on GGX microfacets as described in "Microfacet Models for Refraction
+/// through Rough Surfaces" by Walter et al.
+pub struct RoughDielectric {
+    ior:       f32,
+    roughness: Roughness,
+}
+
+/// Scattering at a hit point: shading frame on the side the ray came from, local direction
+/// towards the ray origin, relative index of refraction of the far side and the microfacet
+/// distribution.
+struct Interface {
+    frame:        Frame,
+    wo:           Vec3,
+    eta:          f32,
+    distribution: Ggx,
+}
+
+impl RoughDielectric {
+    /// Creates dielectric with roughness given by the average of `roughness` texture color
+    /// channels, clamped to the [0, 1] range.
+    pub fn new(ior: f32, roughness: SharedTexture) -> SharedMaterial {
+        assert!(ior > 0.0, "Index of refraction must be positive.");
+
+        super::make_shared(Self {
+            ior,
+            roughness: Roughness::Texture(roughness),
+        })
+    }
+
+    pub fn new_solid(ior: f32, roughness: f32) -> SharedMaterial {
+        assert!(ior > 0.0, "Index of refraction must be positive.");
+        assert!((0.0..=1.0).contains(&roughness), "Roughness must be in the [0, 1] range.");
+
+        super::make_shared(Self {
+            ior,
+            roughness: Roughness::Solid(roughness),
+        })
+    }
+
+    fn roughness(&self, record: &HitRecord) -> f32 {
+        match &self.roughness {
+            Roughness::Texture(roughness) => {
+                let (u, v)    = record.uv();
+                let (r, g, b) = roughness.color(u, v, record.point).extract();
+
+                ((r + g + b) / 3.0).clamp(0.0, 1.0)
+            }
+            Roughness::Solid(roughness)   => *roughness,
+        }
+    }
+
+    fn interface(&self, ray: &Ray, record: &HitRecord) -> Interface {
+        // Normals point outwards, so rays hitting the front side enter the dielectric.
+        let (normal, eta) = if Vec3::dot(ray.direction, record.normal) > 0.0 {
+            (-record.normal, 1.0 / self.ior)
+        } else {
+            (record.normal, self.ior)
+        };
+
+        let frame = Frame::new(normal);
+        let wo    = frame.to_local(-ray.direction);
+
+        Interface {
+            frame,
+            wo,
+            eta,
+            distribution: Ggx::new(self.roughness(record)),
+        }
+    }
+}
+
+/// Normal of the microfacet which scatters `wo` into `wi`, facing the `wo` side. Returns
+/// `None` if no microfacet facing `wo` scatters in that direction.
+fn half_vector(wo: Vec3, wi: Vec3, eta: f32) -> Option<Vec3> {
+    let reflection = wi.extract().2 > 0.0;
+
+    let m = if reflection { wo + wi } else { wo + wi * eta };
+
+    if m.length_sqr() == 0.0 {
+        return None;
+    }
+
+    let m = m.normalized();
+    let m = if m.extract().2 < 0.0 { -m } else { m };
+
+    // Microfacet must face `wo` and lie on the correct side of `wi`.
+    if Vec3::dot(wo, m) <= 0.0 || (Vec3::dot(wi, m) > 0.0) != reflection {
+        return None;
+    }
+
+    Some(m)
+}
+
+impl Material for RoughDielectric {
+    fn scatter(&self, ray: &Ray, record: &HitRecord, rng: &mut Rng) -> Option<(Vec3, Ray)> {
+        let Interface { frame, wo, eta, distribution } = self.interface(ray, record);
+
+        if wo.extract().2 <= 0.0 {
+            return None;
+        }
+
+        let m       = distribution.sample_visible(wo, rng.rand(), rng.rand())?;
+        let fresnel = math::fresnel_dielectric(Vec3::dot(wo, m), eta);
+
+        // Choosing between reflection and refraction by the Fresnel term cancels it out of
+        // the sample weight, which is then the same for both.
+        let wi = if rng.rand::<f32>() < fresnel {
+            let wi = math::reflect(-wo, m);
+
+            if wi.extract().2 <= 0.0 {
+                return None;
+            }
+
+            wi
+        } else {
+            let wi = math::refract(-wo, m, 1.0 / eta)?;
+
+            if wi.extract().2 >= 0.0 {
+                return None;
+            }
+
+            wi
+        };
+
+        let weight    = distribution.masking_shadowing(wo, wi) / distribution.masking(wo);
+        let scattered = Ray::new(record.point, frame.to_world(wi));
+
+        // Rounding can keep nearly horizontal microfacets from being recovered from the
+        // scattered direction, `evaluate` gives those zero PDF so they must not be sampled.
+        half_vector(wo, frame.to_local(scattered.direction), eta)?;
+
+        Some((Vec3::fill(weight), scattered))
+    }
+
+    fn evaluate(&self, ray: &Ray, record: &HitRecord, direction: Vec3) -> Option<(Vec3, f32)> {
+        let Interface { frame, wo, eta, distribution } = self.interface(ray, record);
+
+        let wi             = frame.to_local(direction);
+        let (cos_o, cos_i) = (wo.extract().2, wi.extract().2);
+
+        if cos_o <= 0.0 || cos_i == 0.0 {
+            return Some((Vec3::zero(), 0.0));
+        }
+
+        let reflection = cos_i > 0.0;
+
+        let m = match half_vector(wo, wi, eta) {
+            Some(m) => m,
+            None    => return Some((Vec3::zero(), 0.0)),
+        };
+
+        let (dot_o, dot_i) = (Vec3::dot(wo, m), Vec3::dot(wi, m));
+
+        let fresnel = math::fresnel_dielectric(dot_o, eta);
+        let d       = distribution.distribution(m);
+        let g       = distribution.masking_shadowing(wo, wi);
+        let visible = distribution.visible_pdf(wo, m);
+
+        // Both are multiplied by the Jacobian of the mapping from microfacet normals to
+        // scattered directions.
+        let (bsdf, pdf) = if reflection {
+            let jacobian = 1.0 / (4.0 * dot_o);
+
+            (fresnel * d * g * dot_o * jacobian / cos_o, fresnel * visible * jacobian)
+        } else {
+            let denom    = dot_o + eta * dot_i;
+            let jacobian = eta * eta * -dot_i / (denom * denom);
+
+            ((1.0 - fresnel) * d * g * dot_o * jacobian / cos_o,
+             (1.0 - fresnel) * visible * jacobian)
+        };
+
+        Some((Vec3::fill(bsdf), pdf))
+    }
+}
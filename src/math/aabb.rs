@@ -14,6 +14,11 @@ impl AABB {
         }
     }
 
+    /// Creates box which contains nothing, enclosing it with another box gives that box.
+    pub fn empty() -> Self {
+        Self::new(Vec3::fill(f32::INFINITY), Vec3::fill(f32::NEG_INFINITY))
+    }
+
     pub fn extent(&self) -> Vec3 {
         self.max - self.min
     }
@@ -42,9 +47,9 @@ impl AABB {
         AABB::new(bbmin, bbmax)
     }
 
-    pub fn volume(&self) -> f32 {
+    pub fn surface_area(&self) -> f32 {
         let (x, y, z) = self.extent().extract();
 
-        (x * y * z).abs()
+        2.0 * (x * y + y * z + z * x)
     }
 }
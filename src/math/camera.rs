@@ -1,5 +1,34 @@
 use super::{Vec3, Ray};
 
+/// Resolution independent camera placement.
+#[derive(Copy, Clone)]
+pub struct CameraSettings {
+    pub eye:    Vec3,
+    pub target: Vec3,
+    pub up:     Vec3,
+    /// Vertical field of view in degrees.
+    pub fov:    f32,
+}
+
+impl CameraSettings {
+    /// Creates camera rendering image of given size.
+    pub fn camera(&self, width: usize, height: usize) -> Camera {
+        Camera::new(self.eye, self.target, self.up, self.fov, width, height)
+    }
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            eye:    Vec3::new(12.0, 2.0, 3.0),
+            target: Vec3::new(0.0, 0.0, 0.0),
+            up:     Vec3::new(0.0, 1.0, 0.0),
+            fov:    20.0,
+        }
+    }
+}
+
+/// Pinhole camera generating primary rays for an image.
 #[derive(Clone)]
 pub struct Camera {
     lower_left_corner: Vec3,
@@ -11,6 +40,8 @@ pub struct Camera {
 }
 
 impl Camera {
+    /// Creates camera at `eyes` looking at `target` with vertical field of view `fov` (in
+    /// degrees).
     pub fn new(eyes: Vec3, target: Vec3, up: Vec3, fov: f32, width: usize, height: usize) -> Self {
         let aspect_ratio = width as f32 / height as f32;
 
@@ -33,14 +64,20 @@ impl Camera {
         }
     }
 
+    /// Returns ray going through point `u`, `v` of the image plane, where (0, 0) is the
+    /// bottom left corner and (1, 1) is the top right one.
     #[inline(always)]
     pub fn ray(&self, u: f32, v: f32) -> Ray {
         let direction = (self.lower_left_corner + self.horizontal * u + self.vertical * v) -
             self.origin;
 
         // `direction` is almost normalized so it should be fine. Calling normal `new`
-        // will significantly degrade the performance.
-        Ray::new_normalized(self.origin, direction)
+        // will significantly degrade the performance, so it's done only in precise mode.
+        if cfg!(feature = "precise") {
+            Ray::new(self.origin, direction)
+        } else {
+            Ray::new_normalized(self.origin, direction)
+        }
     }
 
     #[inline(always)]
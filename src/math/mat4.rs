@@ -0,0 +1,152 @@
+use super::Vec3;
+
+use std::ops::Mul;
+
+/// Row major 4x4 matrix of an affine transform. Vectors are treated as columns, so in
+/// `a * b` the transform `b` is applied first.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Mat4 {
+    rows: [[f32; 4]; 4],
+}
+
+impl Mat4 {
+    /// Creates matrix from its rows. The last row must be `(0, 0, 0, 1)`.
+    pub fn new(rows: [[f32; 4]; 4]) -> Self {
+        assert!(rows[3] == [0.0, 0.0, 0.0, 1.0], "Matrix must describe an affine transform.");
+
+        Self {
+            rows,
+        }
+    }
+
+    pub fn identity() -> Self {
+        Self::scale(Vec3::fill(1.0))
+    }
+
+    pub fn translation(offset: Vec3) -> Self {
+        let (x, y, z) = offset.extract();
+
+        Self::new([
+            [1.0, 0.0, 0.0, x],
+            [0.0, 1.0, 0.0, y],
+            [0.0, 0.0, 1.0, z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scale(scale: Vec3) -> Self {
+        let (x, y, z) = scale.extract();
+
+        Self::new([
+            [x,   0.0, 0.0, 0.0],
+            [0.0, y,   0.0, 0.0],
+            [0.0, 0.0, z,   0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Counter-clockwise rotation by `angle` radians around unit vector `axis`.
+    pub fn rotation(axis: Vec3, angle: f32) -> Self {
+        let (x, y, z)  = axis.extract();
+        let (sin, cos) = angle.sin_cos();
+        let t          = 1.0 - cos;
+
+        Self::new([
+            [t * x * x + cos,     t * x * y - sin * z, t * x * z + sin * y, 0.0],
+            [t * x * y + sin * z, t * y * y + cos,     t * y * z - sin * x, 0.0],
+            [t * x * z - sin * y, t * y * z + sin * x, t * z * z + cos,     0.0],
+            [0.0,                 0.0,                 0.0,                 1.0],
+        ])
+    }
+
+    /// Returns inverse of the matrix or `None` if it is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.rows;
+
+        // Inverse of the linear part from its adjugate, translation is then undone by the
+        // inverted linear part.
+        let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| {
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+
+        let adjugate = [
+            [cofactor(1, 2, 1, 2), -cofactor(0, 2, 1, 2), cofactor(0, 1, 1, 2)],
+            [-cofactor(1, 2, 0, 2), cofactor(0, 2, 0, 2), -cofactor(0, 1, 0, 2)],
+            [cofactor(1, 2, 0, 1), -cofactor(0, 2, 0, 1), cofactor(0, 1, 0, 1)],
+        ];
+
+        let determinant = m[0][0] * adjugate[0][0] + m[0][1] * adjugate[1][0] +
+            m[0][2] * adjugate[2][0];
+
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        let mut rows = [[0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 0.0],
+                        [0.0, 0.0, 0.0, 1.0]];
+
+        for i in 0..3 {
+            for j in 0..3 {
+                rows[i][j] = adjugate[i][j] / determinant;
+            }
+
+            rows[i][3] = -(0..3).map(|j| rows[i][j] * m[j][3]).sum::<f32>();
+        }
+
+        Some(Self {
+            rows,
+        })
+    }
+
+    /// Applies the whole transform to point `p`.
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        let translation = Vec3::new(self.rows[0][3], self.rows[1][3], self.rows[2][3]);
+
+        self.transform_vector(p) + translation
+    }
+
+    /// Applies only the linear part of the transform to direction `v`.
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let row = |i: usize| {
+            let [x, y, z, _] = self.rows[i];
+
+            Vec3::dot(Vec3::new(x, y, z), v)
+        };
+
+        Vec3::new(row(0), row(1), row(2))
+    }
+
+    /// Applies transposed linear part of the transform to normal `n`. Surface normals are
+    /// transformed by calling this on the inverse of the surface transform.
+    pub fn transform_normal(&self, n: Vec3) -> Vec3 {
+        let column = |j: usize| {
+            Vec3::dot(Vec3::new(self.rows[0][j], self.rows[1][j], self.rows[2][j]), n)
+        };
+
+        Vec3::new(column(0), column(1), column(2))
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Mul for Mat4 {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        let mut rows = [[0.0; 4]; 4];
+
+        for (i, row) in rows.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.rows[i][k] * other.rows[k][j]).sum();
+            }
+        }
+
+        Self {
+            rows,
+        }
+    }
+}
@@ -1,27 +1,53 @@
+//! Vector math and geometric helpers.
+
 mod vec;
 mod ray;
 mod aabb;
+mod mat4;
 mod camera;
 
 pub use vec::Vec3;
 pub use ray::Ray;
 pub use aabb::AABB;
-pub use camera::Camera;
+pub use mat4::Mat4;
+pub use camera::{Camera, CameraSettings};
 
 use crate::rng::Rng;
 
-pub fn random_in_unit_sphere(rng: &mut Rng) -> Vec3 {
-    let x = rng.rand_range(-1.0, 1.0);
-    let y = rng.rand_range(-1.0, 1.0);
-    let z = rng.rand_range(-1.0, 1.0);
+/// Returns random unit vector, uniformly distributed over the sphere.
+pub fn random_unit_vector(rng: &mut Rng) -> Vec3 {
+    // Normalizing a random point in a cube would favour directions towards its corners.
+    let z   = rng.rand_range(-1.0f32, 1.0);
+    let phi = rng.rand::<f32>() * 2.0 * std::f32::consts::PI;
+
+    let r          = (1.0 - z * z).max(0.0).sqrt();
+    let (sin, cos) = phi.sin_cos();
+
+    Vec3::new(r * cos, r * sin, z)
+}
+
+/// Builds two unit vectors which together with unit vector `n` form an orthonormal basis.
+pub fn orthonormal_basis(n: Vec3) -> (Vec3, Vec3) {
+    // "Building an Orthonormal Basis, Revisited" by Duff et al.
+    let (x, y, z) = n.extract();
 
-    Vec3::new(x, y, z).normalized()
+    let sign = 1.0f32.copysign(z);
+    let a    = -1.0 / (sign + z);
+    let b    = x * y * a;
+
+    let u = Vec3::new(1.0 + sign * x * x * a, sign * b, -sign * x);
+    let v = Vec3::new(b, sign + y * y * a, -y);
+
+    (u, v)
 }
 
+/// Reflects `direction` about `normal`.
 pub fn reflect(direction: Vec3, normal: Vec3) -> Vec3 {
     direction - (normal * Vec3::dot(direction, normal) * 2.0)
 }
 
+/// Refracts unit `direction` through surface with unit `normal`. Returns `None` on total
+/// internal reflection.
 pub fn refract(direction: Vec3, normal: Vec3, ni_over_nt: f32) -> Option<Vec3> {
     let dt = Vec3::dot(direction, normal);
     let d  = 1.0 - ni_over_nt * ni_over_nt * (1.0 - dt * dt);
@@ -33,6 +59,50 @@ pub fn refract(direction: Vec3, normal: Vec3, ni_over_nt: f32) -> Option<Vec3> {
     }
 }
 
+/// Fresnel reflectance of unpolarized light coming from vacuum onto a conductor with complex
+/// index of refraction `eta + ik`, per color channel. `cosine` is between the incident
+/// direction and the normal.
+pub fn fresnel_conductor(cosine: f32, eta: Vec3, k: Vec3) -> Vec3 {
+    let cos2 = cosine * cosine;
+    let sin2 = Vec3::fill(1.0 - cos2);
+
+    let eta2 = eta * eta;
+    let k2   = k * k;
+
+    let t0    = eta2 - k2 - sin2;
+    let a2_b2 = (t0 * t0 + eta2 * k2 * 4.0).sqrt();
+    let t1    = a2_b2 + Vec3::fill(cos2);
+    let a     = ((a2_b2 + t0) * 0.5).sqrt();
+    let t2    = a * (2.0 * cosine);
+    let rs    = (t1 - t2) / (t1 + t2);
+
+    let t3 = a2_b2 * cos2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+
+    (rs + rp) * 0.5
+}
+
+/// Fresnel reflectance of unpolarized light at an interface of two dielectrics. `eta` is the
+/// index of refraction of the far side relative to the side of the incident direction and
+/// positive `cosine` is between the incident direction and the normal.
+pub fn fresnel_dielectric(cosine: f32, eta: f32) -> f32 {
+    let sin2_t = (1.0 - cosine * cosine) / (eta * eta);
+
+    // Total internal reflection.
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+
+    let cos_t = (1.0 - sin2_t).sqrt();
+
+    let rs = (cosine - eta * cos_t) / (cosine + eta * cos_t);
+    let rp = (eta * cosine - cos_t) / (eta * cosine + cos_t);
+
+    (rs * rs + rp * rp) * 0.5
+}
+
+/// Schlick's approximation of Fresnel reflectance.
 pub fn schlick(cosine: f32, ref_idx: f32) -> f32 {
     let r0 = (1.0 - ref_idx) / (1.0 + ref_idx);
     let r0 = r0 * r0;
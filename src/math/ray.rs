@@ -2,8 +2,10 @@ use super::Vec3;
 
 #[derive(Copy, Clone)]
 pub struct Ray {
-    pub origin:    Vec3,
-    pub direction: Vec3,
+    pub origin:     Vec3,
+    pub direction:  Vec3,
+    /// Wavelength in nanometres carried by the ray when rendering spectrally.
+    pub wavelength: Option<f32>,
 }
 
 impl Ray {
@@ -12,6 +14,7 @@ impl Ray {
         Self {
             origin,
             direction,
+            wavelength: None,
         }
     }
 
@@ -19,7 +22,8 @@ impl Ray {
     pub fn new(origin: Vec3, direction: Vec3) -> Self {
         Self {
             origin,
-            direction: direction.normalized(),
+            direction:  direction.normalized(),
+            wavelength: None,
         }
     }
 
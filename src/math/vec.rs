@@ -1,5 +1,6 @@
 use std::ops::{Add, Sub, Mul, Div, Neg, AddAssign, SubAssign, MulAssign, DivAssign};
 
+#[cfg(all(target_arch = "x86_64", not(feature = "scalar")))]
 mod vectorized {
     use std::arch::x86_64::*;
 
@@ -38,8 +39,19 @@ mod vectorized {
     pub fn normalize(vector: ArchVector) -> ArchVector {
         unsafe {
             let length_sqr = fill(sum(mul(vector, vector)));
+            let rsqrt      = _mm_rsqrt_ps(length_sqr);
 
-            _mm_mul_ps(vector, _mm_rsqrt_ps(length_sqr))
+            // `rsqrt` is accurate only to about 12 bits, one Newton-Raphson iteration brings
+            // it close to full single precision.
+            #[cfg(feature = "precise")]
+            let rsqrt = {
+                let half_length_sqr = _mm_mul_ps(length_sqr, fill(0.5));
+                let rsqrt_sqr       = _mm_mul_ps(rsqrt, rsqrt);
+
+                _mm_mul_ps(rsqrt, _mm_sub_ps(fill(1.5), _mm_mul_ps(half_length_sqr, rsqrt_sqr)))
+            };
+
+            _mm_mul_ps(vector, rsqrt)
         }
     }
 
@@ -52,6 +64,68 @@ mod vectorized {
     pub fn sqrt(a: ArchVector)               -> ArchVector { unsafe { _mm_sqrt_ps(a) } }
 }
 
+/// Portable implementation for targets without SSE.
+#[cfg(any(not(target_arch = "x86_64"), feature = "scalar"))]
+mod vectorized {
+    #[derive(Copy, Clone)]
+    #[repr(C, align(16))]
+    pub struct ArchVector([f32; 4]);
+
+    #[inline(always)]
+    fn map(a: ArchVector, f: impl Fn(f32) -> f32) -> ArchVector {
+        let [x, y, z, w] = a.0;
+
+        ArchVector([f(x), f(y), f(z), f(w)])
+    }
+
+    #[inline(always)]
+    fn zip(a: ArchVector, b: ArchVector, f: impl Fn(f32, f32) -> f32) -> ArchVector {
+        let ([ax, ay, az, aw], [bx, by, bz, bw]) = (a.0, b.0);
+
+        ArchVector([f(ax, bx), f(ay, by), f(az, bz), f(aw, bw)])
+    }
+
+    pub fn new(x: f32, y: f32, z: f32) -> ArchVector { ArchVector([x, y, z, 0.0]) }
+
+    pub fn fill(v: f32) -> ArchVector { ArchVector([v; 4]) }
+    pub fn zero()       -> ArchVector { ArchVector([0.0; 4]) }
+
+    pub fn extract(vector: ArchVector) -> (f32, f32, f32) {
+        let [x, y, z, _] = vector.0;
+
+        (x, y, z)
+    }
+
+    pub fn sum(vector: ArchVector) -> f32 {
+        let (x, y, z) = extract(vector);
+
+        x + y + z
+    }
+
+    pub fn normalize(vector: ArchVector) -> ArchVector {
+        let length = sum(mul(vector, vector)).sqrt();
+
+        map(vector, |v| v / length)
+    }
+
+    pub fn add(a: ArchVector, b: ArchVector) -> ArchVector { zip(a, b, |a, b| a + b) }
+    pub fn sub(a: ArchVector, b: ArchVector) -> ArchVector { zip(a, b, |a, b| a - b) }
+    pub fn mul(a: ArchVector, b: ArchVector) -> ArchVector { zip(a, b, |a, b| a * b) }
+    pub fn div(a: ArchVector, b: ArchVector) -> ArchVector { zip(a, b, |a, b| a / b) }
+
+    // SSE `min` and `max` return the second operand if either of them is NaN while `f32::min`
+    // and `f32::max` would return the number. Match SSE so BVH traversal behaves the same.
+    pub fn min(a: ArchVector, b: ArchVector) -> ArchVector {
+        zip(a, b, |a, b| if a < b { a } else { b })
+    }
+
+    pub fn max(a: ArchVector, b: ArchVector) -> ArchVector {
+        zip(a, b, |a, b| if a > b { a } else { b })
+    }
+
+    pub fn sqrt(a: ArchVector)               -> ArchVector { map(a, f32::sqrt) }
+}
+
 use vectorized::ArchVector;
 
 #[derive(Copy, Clone)]
@@ -0,0 +1,98 @@
+//! Minimal OpenEXR writer producing uncompressed scanline images with 32 bit float RGB
+//! channels.
+
+use crate::raytracer::Pixel;
+
+use std::io::{self, BufWriter, Write};
+use std::convert::TryFrom;
+use std::path::Path;
+use std::fs::File;
+
+const MAGIC:   u32 = 20000630;
+const VERSION: u32 = 2;
+
+const PIXEL_TYPE_FLOAT:    i32 = 2;
+const NO_COMPRESSION:      u8  = 0;
+const INCREASING_Y:        u8  = 0;
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+fn box2i(max_x: i32, max_y: i32) -> Vec<u8> {
+    [0, 0, max_x, max_y].iter().flat_map(|v: &i32| v.to_le_bytes()).collect()
+}
+
+pub fn save(path: &Path, width: usize, height: usize, buffer: &[Pixel]) -> io::Result<()> {
+    let too_large = || io::Error::new(io::ErrorKind::InvalidInput,
+                                      "Image is too large for the EXR format.");
+
+    let max_x = i32::try_from(width).map_err(|_| too_large())? - 1;
+    let max_y = i32::try_from(height).map_err(|_| too_large())? - 1;
+
+    // Channels must be sorted alphabetically.
+    const CHANNELS: [(&str, usize); 3] = [("B", 2), ("G", 1), ("R", 0)];
+
+    let mut channels = Vec::new();
+
+    for (name, _) in &CHANNELS {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        channels.extend_from_slice(&PIXEL_TYPE_FLOAT.to_le_bytes());
+        channels.extend_from_slice(&[0, 0, 0, 0]);
+        channels.extend_from_slice(&1i32.to_le_bytes());
+        channels.extend_from_slice(&1i32.to_le_bytes());
+    }
+
+    channels.push(0);
+
+    let mut header = Vec::new();
+
+    header.extend_from_slice(&MAGIC.to_le_bytes());
+    header.extend_from_slice(&VERSION.to_le_bytes());
+
+    attribute(&mut header, "channels",           "chlist",      &channels);
+    attribute(&mut header, "compression",        "compression", &[NO_COMPRESSION]);
+    attribute(&mut header, "dataWindow",         "box2i",       &box2i(max_x, max_y));
+    attribute(&mut header, "displayWindow",      "box2i",       &box2i(max_x, max_y));
+    attribute(&mut header, "lineOrder",          "lineOrder",   &[INCREASING_Y]);
+    attribute(&mut header, "pixelAspectRatio",   "float",       &1.0f32.to_le_bytes());
+    attribute(&mut header, "screenWindowCenter", "v2f",         &[0; 8]);
+    attribute(&mut header, "screenWindowWidth",  "float",       &1.0f32.to_le_bytes());
+
+    header.push(0);
+
+    // Every uncompressed chunk holds a single scanline: its Y coordinate, data size and
+    // then all values of each channel.
+    let line_size  = width * CHANNELS.len() * std::mem::size_of::<f32>();
+    let chunk_size = 8 + line_size;
+    let table_end  = header.len() + height * 8;
+
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    writer.write_all(&header)?;
+
+    for y in 0..height {
+        let offset = (table_end + y * chunk_size) as u64;
+
+        writer.write_all(&offset.to_le_bytes())?;
+    }
+
+    for (y, row) in buffer.chunks_exact(width).enumerate() {
+        writer.write_all(&(y as i32).to_le_bytes())?;
+        writer.write_all(&(line_size as i32).to_le_bytes())?;
+
+        for &(_, channel) in &CHANNELS {
+            for pixel in row {
+                writer.write_all(&pixel[channel].to_le_bytes())?;
+            }
+        }
+    }
+
+    writer.flush()
+}
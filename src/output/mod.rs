@@ -0,0 +1,98 @@
+//! Saving rendered images to disk.
+
+mod exr;
+mod pfm;
+mod tonemap;
+
+pub use tonemap::{DisplayTransform, Tonemap};
+
+use crate::raytracer::Pixel;
+
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::fs::File;
+
+use image::{RgbImage, Rgb};
+use image::hdr::HdrEncoder;
+
+fn flatten_image(mut buffer: Vec<[u8; 3]>) -> Vec<u8> {
+    let buf = buffer.as_mut_ptr();
+    let len = buffer.len();
+    let cap = buffer.capacity();
+
+    assert!(std::mem::align_of::<u8>() == std::mem::align_of::<[u8; 3]>());
+
+    std::mem::forget(buffer);
+
+    unsafe {
+        Vec::from_raw_parts(buf as *mut u8, len * 3, cap * 3)
+    }
+}
+
+fn image_error(err: image::ImageError) -> io::Error {
+    io::Error::other(err)
+}
+
+fn save_ldr(path: &Path, width: usize, height: usize, buffer: &[Pixel],
+            display: &DisplayTransform) -> io::Result<()> {
+    let pixels = buffer.iter().map(|&pixel| display.apply(pixel)).collect();
+
+    RgbImage::from_raw(width as u32, height as u32, flatten_image(pixels))
+        .expect("Failed to create image buffer for the output image.")
+        .save(path)
+        .map_err(image_error)
+}
+
+fn save_radiance(path: &Path, width: usize, height: usize, buffer: &[Pixel]) -> io::Result<()> {
+    let pixels: Vec<Rgb<f32>> = buffer.iter().map(|pixel| Rgb(*pixel)).collect();
+
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    HdrEncoder::new(&mut writer)
+        .encode(&pixels, width, height)
+        .map_err(image_error)?;
+
+    writer.flush()
+}
+
+/// Saves linear radiance `buffer` to `path`. Format of the image is determined by its
+/// extension: `.exr`, `.pfm` and `.hdr` files store the radiance as is, all other formats
+/// supported by the `image` crate get display values produced by `display`.
+///
+/// The image is written to a temporary file first and then moved over `path`, so readers
+/// never see a partially written image.
+pub fn save(path: impl AsRef<Path>, width: usize, height: usize, buffer: &[Pixel],
+            display: &DisplayTransform) -> io::Result<()> {
+    let path = path.as_ref();
+
+    assert!(buffer.len() == width * height, "Image buffer size doesn't match its dimensions.");
+
+    let file_name = path.file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Path has no file name."))?;
+
+    // Keep the extension so the temporary file is written in the same format.
+    let mut temporary_name = std::ffi::OsString::from(".");
+    temporary_name.push(file_name);
+
+    let temporary = path.with_file_name(temporary_name);
+
+    let extension = path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+
+    let result = match extension.as_deref() {
+        Some("exr") => exr::save(&temporary, width, height, buffer),
+        Some("pfm") => pfm::save(&temporary, width, height, buffer),
+        Some("hdr") => save_radiance(&temporary, width, height, buffer),
+        _           => save_ldr(&temporary, width, height, buffer, display),
+    };
+
+    match result {
+        Ok(())   => std::fs::rename(&temporary, path),
+        Err(err) => {
+            let _ = std::fs::remove_file(&temporary);
+
+            Err(err)
+        }
+    }
+}
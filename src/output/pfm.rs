@@ -0,0 +1,23 @@
+use crate::raytracer::Pixel;
+
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::fs::File;
+
+/// Writes portable float map. Scanlines are stored from bottom to top, negative scale
+/// marks little endian data.
+pub fn save(path: &Path, width: usize, height: usize, buffer: &[Pixel]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    write!(writer, "PF\n{} {}\n-1.0\n", width, height)?;
+
+    for row in buffer.chunks_exact(width).rev() {
+        for pixel in row {
+            for channel in pixel {
+                writer.write_all(&channel.to_le_bytes())?;
+            }
+        }
+    }
+
+    writer.flush()
+}
@@ -0,0 +1,149 @@
+use crate::Vec3;
+use crate::raytracer::Pixel;
+
+/// Operator compressing scene radiance to the displayable [0, 1] range.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Tonemap {
+    /// Clips everything above 1.
+    Clamp,
+    /// Per channel `c / (1 + c)`.
+    Reinhard,
+    /// Reinhard which maps given white point (and everything brighter) to 1.
+    ExtendedReinhard(f32),
+    /// Stephen Hill's fit of the ACES reference rendering and sRGB output transforms.
+    Aces,
+    /// Minimal AgX approximation which desaturates highlights instead of skewing their hue.
+    Agx,
+}
+
+/// Conversion of linear radiance to 8 bit sRGB display values.
+#[derive(Copy, Clone, Debug)]
+pub struct DisplayTransform {
+    /// Exposure adjustment in stops applied before tone mapping.
+    pub exposure: f32,
+    pub tonemap:  Tonemap,
+}
+
+impl Default for DisplayTransform {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            tonemap:  Tonemap::Clamp,
+        }
+    }
+}
+
+fn transform(rows: &[[f32; 3]; 3], color: Vec3) -> Vec3 {
+    let row = |index: usize| {
+        let [x, y, z] = rows[index];
+
+        Vec3::dot(Vec3::new(x, y, z), color)
+    };
+
+    Vec3::new(row(0), row(1), row(2))
+}
+
+fn map(color: Vec3, f: impl Fn(f32) -> f32) -> Vec3 {
+    let (r, g, b) = color.extract();
+
+    Vec3::new(f(r), f(g), f(b))
+}
+
+fn aces(color: Vec3) -> Vec3 {
+    // sRGB => XYZ => D65_2_D60 => AP1 => RRT_SAT
+    const INPUT: [[f32; 3]; 3] = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ];
+
+    // ODT_SAT => XYZ => D60_2_D65 => sRGB
+    const OUTPUT: [[f32; 3]; 3] = [
+        [ 1.60475, -0.53108, -0.07367],
+        [-0.10208,  1.10813, -0.00605],
+        [-0.00327, -0.07276,  1.07602],
+    ];
+
+    let color = transform(&INPUT, color);
+    let color = map(color, |v| {
+        let a = v * (v + 0.0245786) - 0.000090537;
+        let b = v * (0.983729 * v + 0.432951) + 0.238081;
+
+        a / b
+    });
+
+    transform(&OUTPUT, color)
+}
+
+fn agx(color: Vec3) -> Vec3 {
+    const INSET: [[f32; 3]; 3] = [
+        [0.8424791, 0.0784336, 0.0792237],
+        [0.0423282, 0.8784686, 0.0791661],
+        [0.0423757, 0.0784336, 0.879143],
+    ];
+
+    const OUTSET: [[f32; 3]; 3] = [
+        [ 1.196879,  -0.0980209, -0.0990297],
+        [-0.0528969,  1.1519031, -0.0989612],
+        [-0.0529716, -0.0980435,  1.1510737],
+    ];
+
+    const MIN_EV: f32 = -12.47393;
+    const MAX_EV: f32 =   4.026069;
+
+    let color = transform(&INSET, color);
+
+    // Log2 encoding followed by a polynomial fit of the AgX base contrast curve.
+    let color = map(color, |v| {
+        let x  = (v.max(1e-10).log2().clamp(MIN_EV, MAX_EV) - MIN_EV) / (MAX_EV - MIN_EV);
+        let x2 = x * x;
+        let x4 = x2 * x2;
+
+        15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 +
+            0.1191 * x - 0.00232
+    });
+
+    // The curve produces display encoded values, linearize them for the sRGB transfer.
+    map(transform(&OUTSET, color), |v| v.max(0.0).powf(2.2))
+}
+
+/// Exact sRGB opto-electronic transfer function.
+fn srgb_oetf(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+impl DisplayTransform {
+    /// Returns linear display color in the [0, 1] range.
+    pub fn tonemap(&self, pixel: Pixel) -> Vec3 {
+        let [r, g, b] = pixel;
+
+        // Negative and NaN values would break the operators, `max` turns NaNs into zero.
+        let color = Vec3::new(r.max(0.0), g.max(0.0), b.max(0.0)) * self.exposure.exp2();
+
+        let color = match self.tonemap {
+            Tonemap::Clamp                   => color,
+            Tonemap::Reinhard                => color / (color + Vec3::fill(1.0)),
+            Tonemap::ExtendedReinhard(white) => {
+                let numerator = color * (Vec3::fill(1.0) + color / (white * white));
+
+                numerator / (color + Vec3::fill(1.0))
+            }
+            Tonemap::Aces                    => aces(color),
+            Tonemap::Agx                     => agx(color),
+        };
+
+        map(color, |v| v.clamp(0.0, 1.0))
+    }
+
+    pub fn apply(&self, pixel: Pixel) -> [u8; 3] {
+        let (r, g, b) = self.tonemap(pixel).extract();
+
+        let quantize = |value: f32| (srgb_oetf(value) * 255.0 + 0.5) as u8;
+
+        [quantize(r), quantize(g), quantize(b)]
+    }
+}
@@ -1,9 +1,12 @@
+//! Thread pool splitting an image buffer between all logical processors.
+
 mod processors;
 
 use std::thread::{self, JoinHandle};
 use std::sync::mpsc::{self, Receiver};
 use std::sync::{Arc, Mutex, Condvar, Barrier};
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::ops::Range;
 
 type Coordinate            = usize;
 type WorkItem              = PixelRange;
@@ -58,6 +61,9 @@ struct State<C, P, L> {
     barrier: Barrier,
 }
 
+/// Pool of threads pinned to logical processors. Context `C` is shared between all threads,
+/// `P` is the type of buffer items (usually pixels) and `L` is thread local state created for
+/// every render.
 pub struct ParallelRenderer<C, P, L> {
     state:   Arc<State<C, P, L>>,
     threads: Vec<JoinHandle<()>>,
@@ -67,9 +73,10 @@ pub struct ParallelRenderer<C, P, L> {
 
 impl<C, P, L> ParallelRenderer<C, P, L> 
     where C: 'static + Send + Sync,
-          P: 'static + Send + Sync + Copy,
+          P: 'static + Send + Sync,
           L: 'static + Default,
 {
+    /// Spawns one thread for every logical processor.
     pub fn new() -> Self {
         let processors   = processors::logical();
         let thread_count = processors.len();
@@ -152,6 +159,9 @@ impl<C, P, L> ParallelRenderer<C, P, L>
         }
     }
 
+    /// Splits `buffer` into ranges and renders them on all threads using `callback`, which
+    /// gets the context, thread local state, index of the first pixel in the range and the
+    /// range itself. Returns once the whole buffer is rendered.
     pub fn render<F>(&mut self, context: &C, buffer: &mut [P], callback: F)
         where F: Fn(&C, &mut L, Coordinate, &mut [P]) + 'static + Send + Sync
     {
@@ -160,24 +170,22 @@ impl<C, P, L> ParallelRenderer<C, P, L>
         {
             let pixel_queue = {
                 let range_count      = self.threads.len() * 64;
-                let pixels_per_range = (pixel_count + range_count - 1) / range_count;
+                let pixels_per_range = pixel_count.div_ceil(range_count);
 
                 let mut ranges = Vec::with_capacity(range_count);
 
+                // Small buffers may not have enough pixels for all ranges, so the last ones
+                // are shorter or left out.
                 for idx in 0..range_count {
                     let start = pixels_per_range * idx;
 
-                    let outside_screen = if idx + 1 == range_count {
-                        (pixels_per_range * range_count).checked_sub(pixel_count).unwrap()
-                    } else {
-                        0
-                    };
-
-                    let size = pixels_per_range.checked_sub(outside_screen).unwrap();
+                    if start >= pixel_count {
+                        break;
+                    }
 
                     ranges.push(PixelRange {
                         start,
-                        size,
+                        size: pixels_per_range.min(pixel_count - start),
                     });
                 }
 
@@ -198,9 +206,40 @@ impl<C, P, L> ParallelRenderer<C, P, L>
         }
 
         for _ in 0..self.threads.len() {
-            let _ = self.done_rx.recv().unwrap();
+            self.done_rx.recv().unwrap();
         }
     }
+
+    /// Runs successive renders over the whole `buffer`, one for every pass in `passes`.
+    /// `callback` additionally gets the index of the current pass and `pass_done` is called
+    /// on the calling thread after every pass with the number of finished passes.
+    pub fn render_passes<F, D>(&mut self, context: &C, buffer: &mut [P], passes: Range<usize>,
+                               callback: F, mut pass_done: D)
+        where F: Fn(&C, &mut L, usize, Coordinate, &mut [P]) + 'static + Send + Sync,
+              D: FnMut(usize, &[P])
+    {
+        let callback = Arc::new(callback);
+
+        for pass in passes {
+            let callback = callback.clone();
+
+            self.render(context, buffer, move |context, local, start, pixels| {
+                callback(context, local, pass, start, pixels)
+            });
+
+            pass_done(pass + 1, buffer);
+        }
+    }
+}
+
+impl<C, P, L> Default for ParallelRenderer<C, P, L>
+    where C: 'static + Send + Sync,
+          P: 'static + Send + Sync,
+          L: 'static + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<C, P, L> Drop for ParallelRenderer<C, P, L> {
@@ -1,8 +1,7 @@
-use std::collections::BTreeSet;
-
+#[cfg(any(target_os = "linux", target_os = "windows"))]
 fn remove_hyperthreads<T, Y: Ord>(processors: &mut Vec<T>,
                                   mut get_core_id: impl FnMut(&T) -> Y) {
-    let mut physical_processors = BTreeSet::new();
+    let mut physical_processors = std::collections::BTreeSet::new();
 
     processors.retain(|processor| {
         physical_processors.insert(get_core_id(processor))
@@ -45,7 +44,7 @@ mod linux {
         let cpuinfo = File::open("/proc/cpuinfo").expect("Failed to open `cpuinfo` file.");
         let cpuinfo = BufReader::new(cpuinfo);
 
-        for line in cpuinfo.lines().filter_map(|line| line.ok()) {
+        for line in cpuinfo.lines().map_while(Result::ok) {
             let mut splitted = line.split(':');
 
             let (key, value) = match (splitted.next(), splitted.next()) {
@@ -94,12 +93,16 @@ mod linux {
         assert!(processors.len() == processor_count,
                 "Number of detected processors differs from the `sysconf` returned value.");
 
-        for processor in &processors {
-            assert!(processor.core_id != INVALID_CORE_ID,
-                    "Processor {} core ID not found.", processor.id);
+        // Some architectures (like ARM) and virtual machines don't report the CPU topology.
+        // Treat every processor as a separate core then.
+        for processor in &mut processors {
+            if processor.core_id == INVALID_CORE_ID {
+                processor.core_id = processor.id;
+            }
 
-            assert!(processor.physical_id != INVALID_PHYSICAL_ID,
-                    "Processor {} physical ID not found.", processor.id);
+            if processor.physical_id == INVALID_PHYSICAL_ID {
+                processor.physical_id = 0;
+            }
         }
 
         if !include_hyperthreads {
@@ -293,18 +296,34 @@ mod windows {
     }
 }
 
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+mod fallback {
+    #[derive(Copy, Clone)]
+    pub struct Processor;
+
+    pub(super) fn processors(_include_hyperthreads: bool) -> Vec<Processor> {
+        // Topology is unknown here, so hyperthreads cannot be filtered out.
+        let count = std::thread::available_parallelism()
+            .map(|count| count.get())
+            .unwrap_or(1);
+
+        vec![Processor; count]
+    }
+
+    pub fn pin_to_processor(_processor: &Processor, _force: bool) {}
+}
+
 #[cfg(target_os = "linux")]
 use linux as os;
 
 #[cfg(target_os = "windows")]
 use windows as os;
 
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+use fallback as os;
+
 pub use os::{Processor, pin_to_processor};
 
 pub fn logical() -> Vec<Processor> {
     os::processors(true)
 }
-
-pub fn physical() -> Vec<Processor> {
-    os::processors(false)
-}
@@ -1,125 +1,343 @@
+//! Path tracing of a scene into a buffer of linear radiance.
+
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Instant;
+use std::ops::Range;
 
 use crate::{Vec3, Ray};
 use crate::rng::Rng;
 use crate::scene::Scene;
+use crate::traceable::HitRecord;
 use crate::math::Camera;
+use crate::parallel_renderer::ParallelRenderer;
+use crate::spectrum::{self, SpectralResponse};
+
+/// Linear radiance of a pixel.
+pub type Pixel = [f32; 3];
+
+/// Fraction of radiance passing through `distance` of medium with `absorption` coefficient.
+fn transmittance(absorption: Vec3, distance: f32) -> Vec3 {
+    let (r, g, b) = (absorption * -distance).extract();
+
+    Vec3::new(r.exp(), g.exp(), b.exp())
+}
+
+/// Power heuristic (with exponent 2) weight of a sample taken with `pdf` when combined with
+/// a strategy with `other_pdf`.
+fn power_heuristic(pdf: f32, other_pdf: f32) -> f32 {
+    let pdf       = pdf * pdf;
+    let other_pdf = other_pdf * other_pdf;
 
-pub type Pixel = [u8; 3];
+    if pdf + other_pdf > 0.0 {
+        pdf / (pdf + other_pdf)
+    } else {
+        0.0
+    }
+}
 
+/// Progress of a render, can be polled from other threads while rendering.
 pub struct Statistics {
-    pub pixels_done: AtomicUsize,
-    start_time:      Instant,
+    pixels_done: AtomicUsize,
+    start_time:  Instant,
 }
 
 impl Statistics {
-    pub fn new() -> Self {
+    fn new() -> Self {
         Self {
             pixels_done: AtomicUsize::new(0),
             start_time:  Instant::now(),
         }
     }
 
+    /// Number of pixels rendered summed over all passes, including passes done before the
+    /// render was resumed.
+    pub fn pixels_done(&self) -> usize {
+        self.pixels_done.load(Ordering::Relaxed)
+    }
+
+    /// Seconds elapsed since the raytracer was created.
     pub fn elapsed(&self) -> f64 {
         self.start_time.elapsed().as_secs_f64()
     }
 }
 
+/// Renders a scene seen by a camera. Image is rendered in passes, every one of them takes
+/// `samples` samples per pixel and there are `samples` passes in total.
 pub struct Raytracer {
-    scene:   Scene,
-    camera:  Camera,
-    samples: usize,
+    scene:    Scene,
+    camera:   Camera,
+    samples:  usize,
+    seed:     u64,
+    spectral: Option<SpectralResponse>,
+    stats:    Statistics,
 }
 
 impl Raytracer {
-    pub fn new(camera: Camera, mut scene: Scene, samples: usize) -> Self {
+    /// Creates raytracer taking `samples`^2 samples per pixel. Rendering the same scene with
+    /// the same `seed` always gives the same image.
+    pub fn new(camera: Camera, mut scene: Scene, samples: usize, seed: u64) -> Self {
+        assert!(samples > 0, "Raytracer needs at least one sample per pixel.");
+
         scene.construct_bvh();
 
         Self {
             camera,
             scene,
             samples,
+            seed,
+            spectral: None,
+            stats:    Statistics::new(),
+        }
+    }
+
+    /// Switches between RGB and spectral rendering. In spectral rendering every camera path
+    /// carries a wavelength, so that dispersive materials split light into colors.
+    ///
+    /// Radiance gathered before a path hits a dispersive material is kept in RGB, which
+    /// already integrates over all wavelengths. Afterwards only the wavelength of the path
+    /// continues, and RGB colors of materials, lights and the environment met on the way
+    /// are turned into spectra using `SpectralResponse::uplift`. Dispersed light therefore
+    /// keeps its color, but colors are mixed in RGB rather than multiplied per wavelength,
+    /// and single samples of spectral colors can have negative channels.
+    pub fn set_spectral(&mut self, spectral: bool) {
+        self.spectral = if spectral { Some(SpectralResponse::new()) } else { None };
+    }
+
+    /// Samples one of the scene lights and returns its contribution to the radiance scattered
+    /// at `record` towards the origin of `ray`.
+    fn sample_direct(&self, ray: &Ray, record: &HitRecord, rng: &mut Rng) -> Vec3 {
+        let (light, selection_pdf) = match self.scene.sample_light(rng) {
+            Some(light) => light,
+            None        => return Vec3::zero(),
+        };
+
+        let sample = match light.sample_light(record.point, rng) {
+            Some(sample) if sample.pdf > 0.0 => sample,
+            _                                => return Vec3::zero(),
+        };
+
+        let (bsdf, bsdf_pdf) = match record.material.evaluate(ray, record, sample.direction) {
+            Some(evaluated) => evaluated,
+            None            => return Vec3::zero(),
+        };
+
+        if bsdf_pdf <= 0.0 {
+            return Vec3::zero();
+        }
+
+        // Stop the shadow ray a bit before the light so it doesn't hit the light itself.
+        let shadow_ray = Ray::new_normalized(record.point, sample.direction);
+        let max_t      = sample.distance * (1.0 - 1e-4);
+
+        if self.scene.occluded(&shadow_ray, max_t) {
+            return Vec3::zero();
         }
+
+        let light_pdf = sample.pdf * selection_pdf;
+        let weight    = power_heuristic(light_pdf, bsdf_pdf);
+
+        bsdf * sample.radiance * (weight / light_pdf)
     }
 
+    /// Traces camera path starting with `ray`. Returns radiance gathered before the path
+    /// scattered off a dispersive material, which is carried by all wavelengths, and
+    /// radiance gathered afterwards, carried only by the wavelength of `ray`.
     #[inline(always)]
-    fn trace_ray(&self, mut ray: Ray, rng: &mut Rng) -> Vec3 {
+    fn trace_ray(&self, mut ray: Ray, rng: &mut Rng) -> (Vec3, Vec3) {
         const MAX_TRACES: usize = 5;
 
         let mut attenuation = Vec3::fill(1.0);
+        let mut radiance    = Vec3::zero();
+
+        // Origin and PDF of the last scattering event if it can be combined with light
+        // sampling. Otherwise emission is accounted for fully when hitting a light.
+        let mut last_scatter: Option<(Vec3, f32)> = None;
+
+        // Absorption coefficient of the medium the ray travels through. Nested media aren't
+        // tracked, leaving any absorbing surface puts the ray back into vacuum.
+        let mut medium: Option<Vec3> = None;
+
+        // Radiance gathered before the first dispersive scattering event. Other wavelengths
+        // would scatter into different directions, so only the one of the ray continues.
+        let mut shared: Option<Vec3> = None;
 
         for _ in 0..MAX_TRACES {
             if let Some(record) = self.scene.trace(&ray) {
+                if let Some(absorption) = medium {
+                    attenuation *= transmittance(absorption, record.t);
+                }
+
+                let emitted = record.material.emitted(&ray, &record);
+
+                let weight = match (last_scatter, record.object) {
+                    (Some((origin, bsdf_pdf)), Some(object)) if object.is_light() => {
+                        let light_pdf = object.light_pdf(origin, &record) *
+                            self.scene.light_selection_pdf();
+
+                        power_heuristic(bsdf_pdf, light_pdf)
+                    }
+                    _ => 1.0,
+                };
+
+                radiance += emitted * attenuation * weight;
+                radiance += self.sample_direct(&ray, &record, rng) * attenuation;
+
                 let scattered = record.material.scatter(&ray, &record, rng);
 
                 if let Some((att_multiplier, new_ray)) = scattered {
+                    last_scatter = record.material
+                        .evaluate(&ray, &record, new_ray.direction)
+                        .map(|(_, pdf)| (record.point, pdf));
+
+                    // Normals point outwards, so rays scattered against them enter the medium.
+                    if let Some(absorption) = record.material.absorption() {
+                        medium = if Vec3::dot(new_ray.direction, record.normal) < 0.0 {
+                            Some(absorption)
+                        } else {
+                            None
+                        };
+                    }
+
+                    if ray.wavelength.is_some() && shared.is_none() &&
+                        record.material.is_dispersive() {
+                        shared   = Some(radiance);
+                        radiance = Vec3::zero();
+                    }
+
                     attenuation *= att_multiplier;
-                    ray          = new_ray;
+                    ray          = Ray { wavelength: ray.wavelength, ..new_ray };
                 } else {
-                    return Vec3::fill(0.0);
+                    break;
                 }
             } else {
+                let color = self.scene.environment().color(ray.direction);
+
+                radiance += color * attenuation;
                 break;
             }
         }
 
-        let t     = 0.5 * (ray.direction.extract().1 + 1.0);
-        let color = Vec3::new(1.0, 1.0, 1.0) * (1.0 - t) + Vec3::new(0.5, 0.7, 1.0) * t;
+        match shared {
+            Some(shared) => (shared, radiance),
+            None         => (radiance, Vec3::zero()),
+        }
+    }
 
-        color * attenuation
+    /// Number of passes needed to take all samples, every pass takes one row of the
+    /// stratified sample grid.
+    pub fn passes(&self) -> usize {
+        self.samples
     }
 
     #[inline(always)]
-    fn trace_pixel(&self, x: usize, y: usize, rng: &mut Rng) -> Vec3 {
+    fn trace_pixel(&self, x: usize, y: usize, pass: usize, rng: &mut Rng) -> Vec3 {
         let mut color_sum = Vec3::zero();
 
+        // With a single sample per axis shoot the ray through the pixel center.
+        let offset = |sample: usize| if self.samples > 1 {
+            sample as f32 / (self.samples - 1) as f32
+        } else {
+            0.5
+        };
+
+        let y = y as f32 + offset(pass);
+        let v = 1.0 - (y / self.height() as f32);
+
         for sx in 0..self.samples {
-            let x = x as f32 + (sx as f32 / (self.samples - 1) as f32);
+            let x = x as f32 + offset(sx);
             let u = x / self.width() as f32;
 
-            for sy in 0..self.samples {
-                let y = y as f32 + (sy as f32 / (self.samples - 1) as f32);
-                let v = 1.0 - (y / self.height() as f32);
+            let mut ray = self.camera.ray(u, v);
 
-                let ray   = self.camera.ray(u, v);
-                let color = self.trace_ray(ray, rng);
+            let color = match &self.spectral {
+                Some(response) => {
+                    // Wavelength is stratified over the row of samples.
+                    let sample     = (sx as f32 + rng.rand::<f32>()) / self.samples as f32;
+                    let wavelength = spectrum::wavelength(sample);
 
-                color_sum += color;
-            }
-        }
+                    ray.wavelength = Some(wavelength);
+
+                    let (shared, single) = self.trace_ray(ray, rng);
 
-        let samples = self.samples * self.samples;
-        let color   = (color_sum / samples as f32).sqrt();
+                    shared + response.weight(wavelength) * response.uplift(single, wavelength)
+                }
+                None => self.trace_ray(ray, rng).0,
+            };
+
+            color_sum += color;
+        }
 
-        color
+        color_sum / self.samples as f32
     }
 
-    pub fn render_fragment(&self, start_pixel: usize, pixels: &mut [Pixel],
-                           stats: &Statistics, rng: &mut Rng) {
+    /// Renders one pass of `pixels` and folds it into their running average. Every pixel of
+    /// every pass gets its own random stream, so the result doesn't depend on how the image
+    /// is split between threads or whether the render was resumed.
+    fn render_fragment(&self, pass: usize, start_pixel: usize, pixels: &mut [Pixel]) {
         const PROGRESS_STEP: usize = 8192;
 
+        assert!(pass < self.passes(), "Pass index is out of range.");
+
         let pixel_count = pixels.len();
+        let weight      = 1.0 / (pass + 1) as f32;
 
         for (i, pixel) in pixels.iter_mut().enumerate() {
             let x = (i + start_pixel) % self.width();
             let y = (i + start_pixel) / self.width();
 
-            let color = self.trace_pixel(x, y, rng);
+            let stream  = (pass * self.pixel_count() + i + start_pixel) as u64;
+            let mut rng = Rng::with_key(self.seed, stream);
 
-            if i > 0 {
-                if i % PROGRESS_STEP == 0 {
-                    stats.pixels_done.fetch_add(PROGRESS_STEP, Ordering::Relaxed);
-                } else if i == pixel_count - 1 {
-                    stats.pixels_done.fetch_add(pixel_count % PROGRESS_STEP,
-                                                Ordering::Relaxed);
-                }
+            let color = self.trace_pixel(x, y, pass, &mut rng);
+
+            if (i + 1) % PROGRESS_STEP == 0 {
+                self.stats.pixels_done.fetch_add(PROGRESS_STEP, Ordering::Relaxed);
             }
 
-            let (r, g, b) = (color * 255.0).extract();
+            let [r, g, b] = *pixel;
+            let average   = Vec3::new(r, g, b);
 
-            *pixel = [r as u8, g as u8, b as u8];
+            *pixel = (average + (color - average) * weight).extract_array();
         }
+
+        self.stats.pixels_done.fetch_add(pixel_count % PROGRESS_STEP, Ordering::Relaxed);
+    }
+
+    /// Renders `passes` of the image on `renderer` threads. `buffer` holds running average of
+    /// all passes rendered so far, so when resuming a render it must contain the result of
+    /// all passes before `passes.start`. `pass_done` is called after every pass with the
+    /// number of passes finished and the image rendered so far.
+    pub fn render(&self, renderer: &mut ParallelRenderer<Self, Pixel, ()>, buffer: &mut [Pixel],
+                  passes: Range<usize>, pass_done: impl FnMut(usize, &[Pixel])) {
+        assert!(buffer.len() == self.pixel_count(), "Buffer size doesn't match image size.");
+        assert!(passes.end <= self.passes(), "Pass range is out of bounds.");
+
+        self.stats.pixels_done.store(passes.start * self.pixel_count(), Ordering::Relaxed);
+
+        renderer.render_passes(self, buffer, passes, |raytracer, _, pass, start_pixel, pixels| {
+            raytracer.render_fragment(pass, start_pixel, pixels);
+        }, pass_done);
+    }
+
+    /// Renders the whole image using all processors and returns its linear radiance.
+    pub fn render_image(&self) -> Vec<Pixel> {
+        let mut renderer = ParallelRenderer::new();
+        let mut buffer   = vec![Pixel::default(); self.pixel_count()];
+
+        self.render(&mut renderer, &mut buffer, 0..self.passes(), |_, _| {});
+
+        buffer
+    }
+
+    /// Scene being rendered. It can be changed between renders, e.g. to move instances in
+    /// the next frame of an animation.
+    pub fn scene_mut(&mut self) -> &mut Scene {
+        &mut self.scene
+    }
+
+    /// Progress of the render.
+    pub fn statistics(&self) -> &Statistics {
+        &self.stats
     }
 
     #[inline(always)]
@@ -1,29 +1,15 @@
+//! Fast pseudo random number generation.
+
+/// Xorshift random number generator.
 pub struct Rng(u64);
 
 impl Rng {
+    /// Creates generator seeded from hardware entropy.
     pub fn new() -> Self {
-        let seed = if is_x86_feature_detected!("rdseed") {
-            loop {
-                let mut seed = 0;
-
-                let result = unsafe {
-                    std::arch::x86_64::_rdseed64_step(&mut seed)
-                };
-
-                if result == 1 {
-                    break seed;
-                }
-            }
-        } else {
-            unsafe {
-                // Hopefully "random" enough.
-                std::arch::x86_64::_rdtsc()
-            }
-        };
-
-        Self::with_seed(seed)
+        Self::with_seed(entropy())
     }
 
+    /// Creates generator with a fixed seed, which must not be zero.
     pub fn with_seed(seed: u64) -> Self {
         let mut rng = Self(seed);
 
@@ -34,6 +20,27 @@ impl Rng {
         rng
     }
 
+    /// Creates generator for stream `key` of `seed`. Generators with different keys produce
+    /// unrelated sequences, which makes results independent of the order the streams are
+    /// consumed in.
+    pub fn with_key(seed: u64, key: u64) -> Self {
+        // SplitMix64 finalizer.
+        fn mix(mut x: u64) -> u64 {
+            x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+            x ^ (x >> 31)
+        }
+
+        // Xorshift never leaves the zero state.
+        match mix(seed ^ mix(key)) {
+            0     => Self::with_seed(0x9e37_79b9_7f4a_7c15),
+            state => Self::with_seed(state),
+        }
+    }
+
+    /// Returns random value, floats are in the [0, 1) range.
     #[inline(always)]
     pub fn rand<T: Random>(&mut self) -> T {
         T::rand(self)
@@ -60,6 +67,48 @@ impl Rng {
     }
 }
 
+#[cfg(target_arch = "x86_64")]
+fn entropy() -> u64 {
+    if is_x86_feature_detected!("rdseed") {
+        loop {
+            let mut seed = 0;
+
+            let result = unsafe {
+                std::arch::x86_64::_rdseed64_step(&mut seed)
+            };
+
+            if result == 1 {
+                break seed;
+            }
+        }
+    } else {
+        unsafe {
+            // Hopefully "random" enough.
+            std::arch::x86_64::_rdtsc()
+        }
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn entropy() -> u64 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    use std::time::SystemTime;
+
+    // `RandomState` keys are randomized by the OS, mix in the time so that generators
+    // created in the same thread differ too.
+    let mut hasher = RandomState::new().build_hasher();
+
+    if let Ok(time) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.write_u128(time.as_nanos());
+    }
+
+    match hasher.finish() {
+        0    => 1,
+        seed => seed,
+    }
+}
+
 impl Default for Rng {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,607 @@
+use crate::{Vec3, Ray};
+use crate::math::AABB;
+use crate::traceable::{HitRecord, Traceable, DynTraceable, TriangleMesh};
+
+use std::convert::TryFrom;
+use std::sync::{Arc, Mutex};
+use std::num::NonZeroUsize;
+
+mod wide;
+
+use wide::WideNode;
+
+/// Cost of visiting a node relative to the cost of intersecting one object.
+const TRAVERSAL_COST: f32 = 1.0;
+
+/// Smaller hierarchies are built on the calling thread.
+const PARALLEL_THRESHOLD: usize = 64 * 1024;
+
+/// Maximum number of objects in subtrees built by worker threads.
+const TASK_SIZE: usize = 4 * 1024;
+
+/// Nodes deeper than this are split at the object median, which limits depth of the tree to
+/// `STACK_SIZE` for any object count representable by `u32`.
+const MEDIAN_SPLIT_DEPTH: usize = 32;
+
+/// Traversal stack size, it never holds more entries than the depth of the tree.
+const STACK_SIZE: usize = MEDIAN_SPLIT_DEPTH + 32;
+
+/// Number of children of BVH nodes. Wider nodes test bounds of all their children at once
+/// using SIMD instructions. All widths find exactly the same intersections.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BvhWidth {
+    Two,
+    Four,
+    Eight,
+}
+
+/// Parameters of the BVH builder.
+#[derive(Copy, Clone, Debug)]
+pub struct BvhSettings {
+    /// Number of bins per axis in which split positions are evaluated.
+    pub bins:      usize,
+    /// Maximum number of objects in a leaf. Nodes with fewer objects become leaves when
+    /// splitting them further isn't worth it.
+    pub leaf_size: usize,
+    /// Node width of the traversed tree. Wide trees are collapsed from the binary one.
+    pub width:     BvhWidth,
+}
+
+impl Default for BvhSettings {
+    fn default() -> Self {
+        Self {
+            bins:      16,
+            leaf_size: 4,
+            width:     BvhWidth::Eight,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Primitive {
+    bbox:     AABB,
+    centroid: Vec3,
+    index:    usize,
+}
+
+/// Node of the tree produced by the builder, before it is flattened.
+enum BuildNode {
+    /// Indices of the objects in the leaf.
+    Leaf(AABB, Box<[usize]>),
+    /// Split along `axis` into children with lower and higher centroids.
+    Split(AABB, usize, Box<(BuildNode, BuildNode)>),
+}
+
+/// Subtree built by one of the worker threads.
+struct BuildTask {
+    primitives: Vec<Primitive>,
+    depth:      usize,
+    node:       Option<BuildNode>,
+}
+
+/// Top of the hierarchy whose subtrees are being built by worker threads.
+enum PartialNode {
+    Leaf(BuildNode),
+    Task(usize),
+    Split(AABB, usize, Box<(PartialNode, PartialNode)>),
+}
+
+fn enclosing_box(primitives: &[Primitive]) -> AABB {
+    primitives.iter().fold(AABB::empty(), |bbox, primitive| {
+        AABB::enclosing_box(&bbox, &primitive.bbox)
+    })
+}
+
+/// Returns bin of `primitive` when `bins` bins cover centroids from `min` to `min + extent`.
+fn bin(primitive: &Primitive, axis: usize, min: f32, extent: f32, bins: usize) -> usize {
+    let offset = primitive.centroid.extract_array()[axis] - min;
+
+    ((offset * (bins as f32 / extent)) as usize).min(bins - 1)
+}
+
+/// Reorders `primitives` so that they can be split into two children using surface area
+/// heuristic and returns size of the first one together with the split axis. Returns `None`
+/// if they should form a leaf.
+fn partition(primitives: &mut [Primitive], bbox: &AABB, depth: usize, settings: &BvhSettings)
+    -> Option<(usize, usize)>
+{
+    let count = primitives.len();
+
+    if count <= 1 {
+        return None;
+    }
+
+    let centroids = primitives.iter().fold(AABB::empty(), |bbox, primitive| {
+        AABB::enclosing_box(&bbox, &AABB::new(primitive.centroid, primitive.centroid))
+    });
+
+    let min    = centroids.min.extract_array();
+    let extent = centroids.extent().extract_array();
+    let bins   = settings.bins;
+
+    if depth >= MEDIAN_SPLIT_DEPTH && count > settings.leaf_size {
+        let axis = (1..3).fold(0, |axis, i| if extent[i] > extent[axis] { i } else { axis });
+
+        primitives.select_nth_unstable_by(count / 2, |a, b| {
+            let a = a.centroid.extract_array()[axis];
+            let b = b.centroid.extract_array()[axis];
+
+            a.total_cmp(&b)
+        });
+
+        return Some((count / 2, axis));
+    }
+
+    // Cost, axis and first bin of the right child of the best split.
+    let mut best: Option<(f32, usize, usize)> = None;
+
+    for axis in 0..3 {
+        if extent[axis] <= 0.0 {
+            continue;
+        }
+
+        let mut boxes  = vec![AABB::empty(); bins];
+        let mut counts = vec![0; bins];
+
+        for primitive in primitives.iter() {
+            let index = bin(primitive, axis, min[axis], extent[axis], bins);
+
+            boxes[index]   = AABB::enclosing_box(&boxes[index], &primitive.bbox);
+            counts[index] += 1;
+        }
+
+        // Costs of right children for every split position, then sweep from the left.
+        let mut right_costs = vec![0.0; bins];
+        let mut right_box   = AABB::empty();
+        let mut right_count = 0;
+
+        for i in (1..bins).rev() {
+            right_box    = AABB::enclosing_box(&right_box, &boxes[i]);
+            right_count += counts[i];
+
+            if right_count > 0 {
+                right_costs[i] = right_box.surface_area() * right_count as f32;
+            }
+        }
+
+        let mut left_box   = AABB::empty();
+        let mut left_count = 0;
+
+        for i in 1..bins {
+            left_box    = AABB::enclosing_box(&left_box, &boxes[i - 1]);
+            left_count += counts[i - 1];
+
+            if left_count == 0 || left_count == count {
+                continue;
+            }
+
+            let cost = left_box.surface_area() * left_count as f32 + right_costs[i];
+
+            if best.is_none_or(|(best_cost, ..)| cost < best_cost) {
+                best = Some((cost, axis, i));
+            }
+        }
+    }
+
+    let (cost, axis, split_bin) = match best {
+        Some(best) => best,
+        None       => {
+            // All centroids are at the same point, so any split is as good as another one.
+            return if count > settings.leaf_size { Some((count / 2, 0)) } else { None };
+        }
+    };
+
+    let cost = TRAVERSAL_COST + cost / bbox.surface_area();
+
+    if count <= settings.leaf_size && cost >= count as f32 {
+        return None;
+    }
+
+    let mut left_count = 0;
+
+    for i in 0..count {
+        if bin(&primitives[i], axis, min[axis], extent[axis], bins) < split_bin {
+            primitives.swap(i, left_count);
+            left_count += 1;
+        }
+    }
+
+    Some((left_count, axis))
+}
+
+fn leaf(primitives: &[Primitive], bbox: AABB) -> BuildNode {
+    BuildNode::Leaf(bbox, primitives.iter().map(|primitive| primitive.index).collect())
+}
+
+fn build(primitives: &mut [Primitive], depth: usize, settings: &BvhSettings) -> BuildNode {
+    let bbox = enclosing_box(primitives);
+
+    match partition(primitives, &bbox, depth, settings) {
+        Some((split, axis)) => {
+            let (left, right) = primitives.split_at_mut(split);
+
+            let left  = build(left, depth + 1, settings);
+            let right = build(right, depth + 1, settings);
+
+            BuildNode::Split(bbox, axis, Box::new((left, right)))
+        }
+        None => leaf(primitives, bbox),
+    }
+}
+
+/// Splits `primitives` until they fit into tasks for worker threads.
+fn build_top(primitives: &mut [Primitive], depth: usize, settings: &BvhSettings,
+             tasks: &mut Vec<BuildTask>) -> PartialNode {
+    if primitives.len() <= TASK_SIZE {
+        tasks.push(BuildTask {
+            primitives: primitives.to_vec(),
+            depth,
+            node:       None,
+        });
+
+        return PartialNode::Task(tasks.len() - 1);
+    }
+
+    let bbox = enclosing_box(primitives);
+
+    match partition(primitives, &bbox, depth, settings) {
+        Some((split, axis)) => {
+            let (left, right) = primitives.split_at_mut(split);
+
+            let left  = build_top(left, depth + 1, settings, tasks);
+            let right = build_top(right, depth + 1, settings, tasks);
+
+            PartialNode::Split(bbox, axis, Box::new((left, right)))
+        }
+        None => PartialNode::Leaf(leaf(primitives, bbox)),
+    }
+}
+
+fn assemble(node: PartialNode, tasks: &mut [BuildTask]) -> BuildNode {
+    match node {
+        PartialNode::Leaf(node)               => node,
+        PartialNode::Task(index)              => tasks[index].node.take().unwrap(),
+        PartialNode::Split(bbox, axis, split) => {
+            let (left, right) = *split;
+
+            BuildNode::Split(bbox, axis, Box::new((assemble(left, tasks), assemble(right, tasks))))
+        }
+    }
+}
+
+/// Builds tree over `objects` using binned surface area heuristic. Large trees are built
+/// using all logical processors.
+fn build_tree(objects: &[Arc<DynTraceable>], settings: &BvhSettings) -> BuildNode {
+    assert!(!objects.is_empty(), "Cannot build BVH without any objects.");
+    assert!(settings.bins >= 2, "BVH builder needs at least two bins.");
+    assert!(settings.leaf_size >= 1 && settings.leaf_size <= u16::MAX as usize,
+            "BVH leaf size must be between 1 and 65535.");
+
+    let mut primitives: Vec<Primitive> = objects.iter()
+        .enumerate()
+        .map(|(index, object)| {
+            let bbox = object.bounding_box();
+
+            Primitive {
+                bbox,
+                centroid: bbox.center(),
+                index,
+            }
+        })
+        .collect();
+
+    if primitives.len() < PARALLEL_THRESHOLD {
+        return build(&mut primitives, 0, settings);
+    }
+
+    let mut tasks = Vec::new();
+    let top       = build_top(&mut primitives, 0, settings, &mut tasks);
+
+    // Result doesn't depend on the thread count, tasks are split the same way.
+    let threads = std::thread::available_parallelism().map_or(1, NonZeroUsize::get)
+        .min(tasks.len());
+    let queue   = Mutex::new(tasks.iter_mut());
+
+    let work = || loop {
+        // The lock is released before the subtree is built.
+        let task = match queue.lock().unwrap().next() {
+            Some(task) => task,
+            None       => break,
+        };
+
+        task.node = Some(build(&mut task.primitives, task.depth, settings));
+    };
+
+    std::thread::scope(|scope| {
+        for _ in 1..threads {
+            scope.spawn(work);
+        }
+
+        work();
+    });
+
+    assemble(top, &mut tasks)
+}
+
+/// Node of the flattened tree. Children of interior nodes are stored depth first: the first
+/// child directly follows its parent.
+#[derive(Copy, Clone)]
+#[repr(C, align(32))]
+struct Node {
+    min:    [f32; 3],
+    /// Index of the first object of a leaf or index of the second child of an interior node.
+    offset: u32,
+    max:    [f32; 3],
+    /// Number of objects in a leaf, zero for interior nodes.
+    count:  u16,
+    /// Split axis of an interior node.
+    axis:   u16,
+}
+
+// Two nodes share a cache line.
+const _: () = assert!(std::mem::size_of::<Node>() == 32);
+
+impl Node {
+    fn new(bbox: &AABB, offset: usize, count: usize, axis: usize) -> Self {
+        Self {
+            min:    bbox.min.extract_array(),
+            max:    bbox.max.extract_array(),
+            offset: u32::try_from(offset).expect("Too many BVH nodes or objects."),
+            count:  count as u16,
+            axis:   axis as u16,
+        }
+    }
+
+    #[inline(always)]
+    fn bounding_box(&self) -> AABB {
+        let [x0, y0, z0] = self.min;
+        let [x1, y1, z1] = self.max;
+
+        AABB::new(Vec3::new(x0, y0, z0), Vec3::new(x1, y1, z1))
+    }
+}
+
+fn flatten(node: BuildNode, objects: &[Arc<DynTraceable>], nodes: &mut Vec<Node>,
+           ordered: &mut Vec<Arc<DynTraceable>>) {
+    match node {
+        BuildNode::Leaf(bbox, indices) => {
+            nodes.push(Node::new(&bbox, ordered.len(), indices.len(), 0));
+            ordered.extend(indices.iter().map(|&index| objects[index].clone()));
+        }
+        BuildNode::Split(bbox, axis, children) => {
+            let index = nodes.len();
+
+            nodes.push(Node::new(&bbox, 0, 0, axis));
+
+            let (left, right) = *children;
+
+            flatten(left, objects, nodes, ordered);
+
+            let offset = nodes.len();
+
+            nodes[index] = Node::new(&bbox, offset, 0, axis);
+
+            flatten(right, objects, nodes, ordered);
+        }
+    }
+}
+
+/// Nodes of the hierarchy in the selected width.
+enum Nodes {
+    Binary(Vec<Node>),
+    Four(Vec<WideNode<4>>),
+    Eight(Vec<WideNode<8>>),
+}
+
+/// Bounding volume hierarchy stored as an array of nodes, objects of every leaf are
+/// contiguous.
+pub struct Bvh {
+    nodes:   Nodes,
+    objects: Vec<Arc<DynTraceable>>,
+    bbox:    AABB,
+}
+
+impl Bvh {
+    pub fn new(objects: Vec<Arc<DynTraceable>>, settings: &BvhSettings) -> Self {
+        let tree = build_tree(&objects, settings);
+
+        let mut nodes   = Vec::new();
+        let mut ordered = Vec::with_capacity(objects.len());
+
+        flatten(tree, &objects, &mut nodes, &mut ordered);
+
+        let bbox = nodes[0].bounding_box();
+
+        let nodes = match settings.width {
+            BvhWidth::Two   => Nodes::Binary(nodes),
+            BvhWidth::Four  => Nodes::Four(wide::collapse(&nodes)),
+            BvhWidth::Eight => Nodes::Eight(wide::collapse(&nodes)),
+        };
+
+        Self {
+            nodes,
+            objects: ordered,
+            bbox,
+        }
+    }
+
+    pub fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+
+    /// Finds the closest intersection with `ray` which lies between `min_t` and `max_t`.
+    pub fn trace(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<HitRecord<'_>> {
+        match &self.nodes {
+            Nodes::Binary(nodes) => self.trace_binary(nodes, ray, min_t, max_t),
+            Nodes::Four(nodes)   => self.trace_wide(nodes, ray, min_t, max_t),
+            Nodes::Eight(nodes)  => self.trace_wide(nodes, ray, min_t, max_t),
+        }
+    }
+
+    fn trace_wide<const N: usize>(&self, nodes: &[WideNode<N>], ray: &Ray, min_t: f32,
+                                  max_t: f32) -> Option<HitRecord<'_>> {
+        let inv_direction = Vec3::fill(1.0) / ray.direction;
+
+        // Wide nodes store only bounds of their children.
+        if !self.bbox.intersect(ray, inv_direction, min_t, max_t) {
+            return None;
+        }
+
+        wide::trace(nodes, &self.objects, ray, min_t, max_t)
+    }
+
+    /// Returns true if `ray` hits any object between `min_t` and `max_t`. Traversal stops at
+    /// the first hit found.
+    pub fn occluded(&self, ray: &Ray, min_t: f32, max_t: f32) -> bool {
+        match &self.nodes {
+            Nodes::Binary(nodes) => self.occluded_binary(nodes, ray, min_t, max_t),
+            Nodes::Four(nodes)   => self.occluded_wide(nodes, ray, min_t, max_t),
+            Nodes::Eight(nodes)  => self.occluded_wide(nodes, ray, min_t, max_t),
+        }
+    }
+
+    fn occluded_wide<const N: usize>(&self, nodes: &[WideNode<N>], ray: &Ray, min_t: f32,
+                                     max_t: f32) -> bool {
+        let inv_direction = Vec3::fill(1.0) / ray.direction;
+
+        self.bbox.intersect(ray, inv_direction, min_t, max_t) &&
+            wide::occluded(nodes, &self.objects, ray, min_t, max_t)
+    }
+
+    fn occluded_binary(&self, nodes: &[Node], ray: &Ray, min_t: f32, max_t: f32) -> bool {
+        let inv_direction = Vec3::fill(1.0) / ray.direction;
+
+        let mut stack = [0u32; STACK_SIZE];
+        let mut size  = 0;
+        let mut index = 0;
+
+        loop {
+            let node = &nodes[index];
+
+            if node.bounding_box().intersect(ray, inv_direction, min_t, max_t) {
+                let offset = node.offset as usize;
+
+                // Any hit ends the search, so children are simply visited in storage order.
+                if node.count == 0 {
+                    stack[size] = offset as u32;
+                    size       += 1;
+                    index      += 1;
+
+                    continue;
+                }
+
+                let objects = &self.objects[offset..offset + node.count as usize];
+
+                if objects.iter().any(|object| object.occluded(ray, min_t, max_t)) {
+                    return true;
+                }
+            }
+
+            if size == 0 {
+                return false;
+            }
+
+            size  -= 1;
+            index  = stack[size] as usize;
+        }
+    }
+
+    fn trace_binary(&self, nodes: &[Node], ray: &Ray, min_t: f32, max_t: f32)
+        -> Option<HitRecord<'_>>
+    {
+        let inv_direction = Vec3::fill(1.0) / ray.direction;
+        let negative      = ray.direction.extract_array().map(|x| x < 0.0);
+
+        let mut stack = [0u32; STACK_SIZE];
+        let mut size  = 0;
+        let mut index = 0;
+
+        let mut closest_distance = max_t;
+        let mut closest_record   = None;
+
+        loop {
+            let node = &nodes[index];
+
+            if node.bounding_box().intersect(ray, inv_direction, min_t, closest_distance) {
+                let offset = node.offset as usize;
+
+                if node.count == 0 {
+                    // Visit the child nearer along the ray first, so that the other one can be
+                    // culled by the closest hit found so far.
+                    let (near, far) = if negative[node.axis as usize] {
+                        (offset, index + 1)
+                    } else {
+                        (index + 1, offset)
+                    };
+
+                    stack[size] = far as u32;
+                    size       += 1;
+                    index       = near;
+
+                    continue;
+                }
+
+                for object in &self.objects[offset..offset + node.count as usize] {
+                    if let Some(record) = object.trace(ray, min_t, closest_distance) {
+                        closest_distance = record.t;
+                        closest_record   = Some(record);
+                    }
+                }
+            }
+
+            if size == 0 {
+                break closest_record;
+            }
+
+            size  -= 1;
+            index  = stack[size] as usize;
+        }
+    }
+}
+
+/// Bottom level acceleration structure: BVH over objects in their own space. It is built
+/// once and can be shared by any number of `Instance`s placing it in the scene.
+pub struct Blas {
+    bvh: Bvh,
+}
+
+impl Blas {
+    pub fn new(objects: Vec<Arc<DynTraceable>>) -> Self {
+        Self::with_settings(objects, &BvhSettings::default())
+    }
+
+    pub fn with_settings(objects: Vec<Arc<DynTraceable>>, settings: &BvhSettings) -> Self {
+        assert!(!objects.is_empty(), "Cannot build BLAS without any objects.");
+
+        Self {
+            bvh: Bvh::new(objects, settings),
+        }
+    }
+
+    /// Builds BLAS over all triangles of `meshes`.
+    pub fn from_meshes(meshes: &[Arc<TriangleMesh>], settings: &BvhSettings) -> Self {
+        Self::with_settings(meshes.iter()
+            .flat_map(TriangleMesh::triangles)
+            .map(|triangle| Arc::new(triangle) as Arc<DynTraceable>)
+            .collect(), settings)
+    }
+}
+
+impl Traceable for Blas {
+    fn trace(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<HitRecord<'_>> {
+        let mut record = self.bvh.trace(ray, min_t, max_t)?;
+
+        // Objects inside aren't part of the scene light list.
+        record.object = None;
+
+        Some(record)
+    }
+
+    fn occluded(&self, ray: &Ray, min_t: f32, max_t: f32) -> bool {
+        self.bvh.occluded(ray, min_t, max_t)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bvh.bounding_box()
+    }
+}
@@ -0,0 +1,378 @@
+use super::{Node, STACK_SIZE};
+use crate::{Vec3, Ray};
+use crate::traceable::{HitRecord, DynTraceable};
+
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+
+/// Every wide node replaces at least one level of the binary tree and leaves at most `N - 1`
+/// siblings on the stack per level, so this is enough for any supported width.
+const WIDE_STACK_SIZE: usize = STACK_SIZE * 7 + 1;
+
+/// Node with up to `N` children. Bounds are stored per axis so that all children are tested
+/// against the ray at once.
+#[derive(Copy, Clone)]
+#[repr(C, align(32))]
+pub(super) struct WideNode<const N: usize> {
+    min:      [[f32; N]; 3],
+    max:      [[f32; N]; 3],
+    /// Index of the child node or of the first object of a leaf child.
+    offset:   [u32; N],
+    /// Number of objects in a leaf child, zero for interior children.
+    count:    [u32; N],
+    /// Near to far order of children for every octant of the ray direction, 4 bits per child.
+    order:    [u32; 8],
+    children: u32,
+}
+
+impl<const N: usize> WideNode<N> {
+    fn empty() -> Self {
+        Self {
+            min:      [[0.0; N]; 3],
+            max:      [[0.0; N]; 3],
+            offset:   [0; N],
+            count:    [0; N],
+            order:    [0; 8],
+            children: 0,
+        }
+    }
+}
+
+/// Collapses flattened binary tree into a tree of `N` wide nodes. Objects stay in the same
+/// order, so leaves still refer to the same ranges of objects.
+pub(super) fn collapse<const N: usize>(binary: &[Node]) -> Vec<WideNode<N>> {
+    assert!(N <= 8, "Wide BVH nodes can have at most 8 children.");
+
+    let mut nodes = Vec::new();
+
+    collapse_node(binary, 0, &mut nodes);
+
+    nodes
+}
+
+fn collapse_node<const N: usize>(binary: &[Node], root: usize,
+                                 nodes: &mut Vec<WideNode<N>>) -> usize {
+    // Pull in descendants of the binary node by opening the largest interior child until the
+    // wide node is full.
+    let mut children = vec![root];
+
+    while children.len() < N {
+        let largest = children.iter()
+            .enumerate()
+            .filter(|(_, &child)| binary[child].count == 0)
+            .max_by(|(_, &a), (_, &b)| {
+                let area_a = binary[a].bounding_box().surface_area();
+                let area_b = binary[b].bounding_box().surface_area();
+
+                area_a.total_cmp(&area_b)
+            });
+
+        let (lane, child) = match largest {
+            Some((lane, &child)) => (lane, child),
+            None                 => break,
+        };
+
+        children.splice(lane..=lane, [child + 1, binary[child].offset as usize]);
+    }
+
+    let index = nodes.len();
+
+    nodes.push(WideNode::empty());
+
+    let mut node = WideNode::empty();
+
+    node.children = children.len() as u32;
+
+    for (lane, &child) in children.iter().enumerate() {
+        let child = &binary[child];
+
+        for axis in 0..3 {
+            node.min[axis][lane] = child.min[axis];
+            node.max[axis][lane] = child.max[axis];
+        }
+
+        node.count[lane] = child.count as u32;
+    }
+
+    // Children are visited in the same order as the binary traversal would visit them, so
+    // both traversals find the same closest hit even when hit distances are equal.
+    for (octant, order) in node.order.iter_mut().enumerate() {
+        let negative = [octant & 1 != 0, octant & 2 != 0, octant & 4 != 0];
+        let mut lanes = Vec::with_capacity(N);
+
+        visiting_order(binary, root, &children, negative, &mut lanes);
+
+        *order = lanes.iter()
+            .enumerate()
+            .fold(0, |order, (position, &lane)| order | ((lane as u32) << (4 * position)));
+    }
+
+    for (lane, &child) in children.iter().enumerate() {
+        node.offset[lane] = if binary[child].count == 0 {
+            collapse_node(binary, child, nodes) as u32
+        } else {
+            binary[child].offset
+        };
+    }
+
+    nodes[index] = node;
+
+    index
+}
+
+fn visiting_order(binary: &[Node], index: usize, children: &[usize], negative: [bool; 3],
+                  lanes: &mut Vec<usize>) {
+    if let Some(lane) = children.iter().position(|&child| child == index) {
+        lanes.push(lane);
+        return;
+    }
+
+    let node = &binary[index];
+
+    let (near, far) = if negative[node.axis as usize] {
+        (node.offset as usize, index + 1)
+    } else {
+        (index + 1, node.offset as usize)
+    };
+
+    visiting_order(binary, near, children, negative, lanes);
+    visiting_order(binary, far, children, negative, lanes);
+}
+
+#[derive(Copy, Clone)]
+struct StackEntry {
+    offset: u32,
+    count:  u32,
+    /// Distance at which the ray enters bounds of the node.
+    entry:  f32,
+}
+
+struct RayLanes {
+    origin:        [f32; 3],
+    inv_direction: [f32; 3],
+}
+
+/// Tests the ray against bounds of all children of `node`. Returns mask of hit children and
+/// their entry distances. Results are the same as those of `AABB::intersect`.
+#[cfg(all(target_arch = "x86_64", not(feature = "scalar")))]
+#[inline(always)]
+fn intersect<const N: usize>(node: &WideNode<N>, ray: &RayLanes, min_t: f32, max_t: f32)
+    -> (u32, [f32; N])
+{
+    use std::arch::x86_64::*;
+
+    let mut entries = [0.0; N];
+    let mut mask    = 0;
+
+    // NaN distances lose in `min` and `max` when they are the first operand, so the
+    // accumulators never become NaN, just like in `AABB::intersect`.
+    #[cfg(target_feature = "avx")]
+    if N == 8 {
+        unsafe {
+            let mut entry = _mm256_set1_ps(min_t);
+            let mut exit  = _mm256_set1_ps(max_t);
+
+            for axis in 0..3 {
+                let origin        = _mm256_set1_ps(ray.origin[axis]);
+                let inv_direction = _mm256_set1_ps(ray.inv_direction[axis]);
+
+                let t0 = _mm256_loadu_ps(node.min[axis].as_ptr());
+                let t1 = _mm256_loadu_ps(node.max[axis].as_ptr());
+                let t0 = _mm256_mul_ps(_mm256_sub_ps(t0, origin), inv_direction);
+                let t1 = _mm256_mul_ps(_mm256_sub_ps(t1, origin), inv_direction);
+
+                entry = _mm256_max_ps(_mm256_min_ps(t0, t1), entry);
+                exit  = _mm256_min_ps(_mm256_max_ps(t0, t1), exit);
+            }
+
+            _mm256_storeu_ps(entries.as_mut_ptr(), entry);
+
+            mask = _mm256_movemask_ps(_mm256_cmp_ps::<_CMP_LT_OQ>(entry, exit)) as u32;
+        }
+
+        return (mask, entries);
+    }
+
+    for first in (0..N).step_by(4) {
+        unsafe {
+            let mut entry = _mm_set1_ps(min_t);
+            let mut exit  = _mm_set1_ps(max_t);
+
+            for axis in 0..3 {
+                let origin        = _mm_set1_ps(ray.origin[axis]);
+                let inv_direction = _mm_set1_ps(ray.inv_direction[axis]);
+
+                let t0 = _mm_loadu_ps(node.min[axis].as_ptr().add(first));
+                let t1 = _mm_loadu_ps(node.max[axis].as_ptr().add(first));
+                let t0 = _mm_mul_ps(_mm_sub_ps(t0, origin), inv_direction);
+                let t1 = _mm_mul_ps(_mm_sub_ps(t1, origin), inv_direction);
+
+                entry = _mm_max_ps(_mm_min_ps(t0, t1), entry);
+                exit  = _mm_min_ps(_mm_max_ps(t0, t1), exit);
+            }
+
+            _mm_storeu_ps(entries.as_mut_ptr().add(first), entry);
+
+            mask |= (_mm_movemask_ps(_mm_cmplt_ps(entry, exit)) as u32) << first;
+        }
+    }
+
+    (mask, entries)
+}
+
+/// Tests the ray against bounds of all children of `node`. Returns mask of hit children and
+/// their entry distances. Results are the same as those of `AABB::intersect`.
+#[cfg(any(not(target_arch = "x86_64"), feature = "scalar"))]
+#[inline(always)]
+fn intersect<const N: usize>(node: &WideNode<N>, ray: &RayLanes, min_t: f32, max_t: f32)
+    -> (u32, [f32; N])
+{
+    // Same NaN handling as `Vec3::min` and `Vec3::max`.
+    let min = |a: f32, b: f32| if a < b { a } else { b };
+    let max = |a: f32, b: f32| if a > b { a } else { b };
+
+    let mut entries = [0.0; N];
+    let mut mask    = 0;
+
+    for (lane, lane_entry) in entries.iter_mut().enumerate() {
+        let mut entry = min_t;
+        let mut exit  = max_t;
+
+        for axis in 0..3 {
+            let t0 = (node.min[axis][lane] - ray.origin[axis]) * ray.inv_direction[axis];
+            let t1 = (node.max[axis][lane] - ray.origin[axis]) * ray.inv_direction[axis];
+
+            entry = max(min(t0, t1), entry);
+            exit  = min(max(t0, t1), exit);
+        }
+
+        *lane_entry = entry;
+
+        if entry < exit {
+            mask |= 1 << lane;
+        }
+    }
+
+    (mask, entries)
+}
+
+/// Returns true if `ray` hits any object between `min_t` and `max_t`. Bounds of the root must
+/// have been already tested by the caller.
+pub(super) fn occluded<const N: usize>(nodes: &[WideNode<N>], objects: &[Arc<DynTraceable>],
+                                       ray: &Ray, min_t: f32, max_t: f32) -> bool {
+    let lanes = RayLanes {
+        origin:        ray.origin.extract_array(),
+        inv_direction: (Vec3::fill(1.0) / ray.direction).extract_array(),
+    };
+
+    // Any hit ends the search, so neither entry distances nor child order matter here.
+    let mut stack = [MaybeUninit::<(u32, u32)>::uninit(); WIDE_STACK_SIZE];
+    let mut size  = 1;
+
+    stack[0] = MaybeUninit::new((0, 0));
+
+    while size > 0 {
+        size -= 1;
+
+        // Entries below `size` were all written before.
+        let (offset, count) = unsafe { stack[size].assume_init() };
+        let offset          = offset as usize;
+
+        if count > 0 {
+            let objects = &objects[offset..offset + count as usize];
+
+            if objects.iter().any(|object| object.occluded(ray, min_t, max_t)) {
+                return true;
+            }
+
+            continue;
+        }
+
+        let node      = &nodes[offset];
+        let (mask, _) = intersect(node, &lanes, min_t, max_t);
+
+        for lane in 0..node.children as usize {
+            if mask & (1 << lane) != 0 {
+                stack[size] = MaybeUninit::new((node.offset[lane], node.count[lane]));
+                size       += 1;
+            }
+        }
+    }
+
+    false
+}
+
+/// Finds the closest intersection with `ray` which lies between `min_t` and `max_t`. Bounds
+/// of the root must have been already tested by the caller.
+pub(super) fn trace<'a, const N: usize>(nodes: &[WideNode<N>], objects: &'a [Arc<DynTraceable>],
+                                        ray: &Ray, min_t: f32, max_t: f32)
+    -> Option<HitRecord<'a>>
+{
+    let direction = ray.direction.extract_array();
+    let octant    = (0..3).fold(0, |octant, axis| {
+        octant | ((direction[axis] < 0.0) as usize) << axis
+    });
+
+    let lanes = RayLanes {
+        origin:        ray.origin.extract_array(),
+        inv_direction: (Vec3::fill(1.0) / ray.direction).extract_array(),
+    };
+
+    let mut stack = [MaybeUninit::<StackEntry>::uninit(); WIDE_STACK_SIZE];
+    let mut size  = 1;
+
+    stack[0] = MaybeUninit::new(StackEntry {
+        offset: 0,
+        count:  0,
+        entry:  f32::NEG_INFINITY,
+    });
+
+    let mut closest_distance = max_t;
+    let mut closest_record   = None;
+
+    while size > 0 {
+        size -= 1;
+
+        // Entries below `size` were all written before.
+        let item = unsafe { stack[size].assume_init() };
+
+        // Closer hit may have been found since the node was pushed.
+        if item.entry >= closest_distance {
+            continue;
+        }
+
+        let offset = item.offset as usize;
+
+        if item.count > 0 {
+            for object in &objects[offset..offset + item.count as usize] {
+                if let Some(record) = object.trace(ray, min_t, closest_distance) {
+                    closest_distance = record.t;
+                    closest_record   = Some(record);
+                }
+            }
+
+            continue;
+        }
+
+        let node            = &nodes[offset];
+        let (mask, entries) = intersect(node, &lanes, min_t, closest_distance);
+        let order           = node.order[octant];
+
+        // Push in reverse so that the nearest child is popped first.
+        for position in (0..node.children).rev() {
+            let lane = ((order >> (4 * position)) & 0xf) as usize;
+
+            if mask & (1 << lane) != 0 {
+                stack[size] = MaybeUninit::new(StackEntry {
+                    offset: node.offset[lane],
+                    count:  node.count[lane],
+                    entry:  entries[lane],
+                });
+
+                size += 1;
+            }
+        }
+    }
+
+    closest_record
+}
@@ -1,15 +1,19 @@
+//! Built-in example scenes. Every generator fills the scene and returns its camera.
+
 use crate::Vec3;
 use crate::rng::Rng;
-use crate::traceable::Sphere;
+use crate::math::CameraSettings;
+use crate::traceable::{Sphere, Plane, Quad, Cuboid};
 use crate::texture::PictureTexture;
-use crate::material::{Metal, Lambertian, Dielectric};
+use crate::environment::ConstantEnvironment;
+use crate::material::{Metal, Lambertian, Dielectric, DiffuseLight};
 use super::Scene;
 
-pub fn simple_scene(scene: &mut Scene) {
+pub fn simple_scene(scene: &mut Scene, _rng: &mut Rng) -> CameraSettings {
     let matte1 = Lambertian::new(PictureTexture::new("earthmap.jpg"));
     let matte2 = Lambertian::new_solid(Vec3::new(0.3, 0.0, 0.0));
     scene.add(Sphere::new(Vec3::new(0.0, 0.0, -1.0), 0.5, &matte1));
-    scene.add(Sphere::new(Vec3::new(0.0, -100.5, -1.0), 100.0, &matte2));
+    scene.add(Plane::new(Vec3::new(0.0, -0.5, 0.0), Vec3::new(0.0, 1.0, 0.0), &matte2));
 
     let metal1 = Metal::new(Vec3::new(0.8, 0.6, 0.2), 0.0);
     let glass1 = Dielectric::new(1.8);
@@ -20,17 +24,17 @@ pub fn simple_scene(scene: &mut Scene) {
 
     let metal2 = Metal::new(Vec3::new(0.1, 1.0, 0.7), 0.1);
     scene.add(Sphere::new(Vec3::new(10.0, 0.0, -10.0), 3.0, &metal2));
+
+    CameraSettings::default()
 }
 
-pub fn random_scene(scene: &mut Scene) {
-    scene.add(Sphere::new(
-        Vec3::new(0.0, -1000.0, 0.0),
-        1000.0,
+pub fn random_scene(scene: &mut Scene, rng: &mut Rng) -> CameraSettings {
+    scene.add(Plane::new(
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
         &Lambertian::new_solid(Vec3::new(0.5, 0.5, 0.5)),
     ));
 
-    let mut rng = Rng::new();
-
     let random_vec = |rng: &mut Rng, min: f32, max: f32| {
         Vec3::new(
             rng.rand_range(min, max),
@@ -51,12 +55,12 @@ pub fn random_scene(scene: &mut Scene) {
                 let choose_mat: f32 = rng.rand();
 
                 let material = if choose_mat < 0.8 {
-                    let albedo = random_vec(&mut rng, 0.0, 1.0) * 
-                        random_vec(&mut rng, 0.0, 1.0);
+                    let albedo = random_vec(rng, 0.0, 1.0) * 
+                        random_vec(rng, 0.0, 1.0);
 
                     Lambertian::new_solid(albedo)
                 } else if choose_mat < 0.95 {
-                    let albedo = random_vec(&mut rng, 0.5, 1.0);
+                    let albedo = random_vec(rng, 0.5, 1.0);
                     let fuzz   = rng.rand_range(0.0, 0.5);
 
                     Metal::new(albedo, fuzz)
@@ -86,4 +90,59 @@ pub fn random_scene(scene: &mut Scene) {
         1.0,
         &Metal::new(Vec3::new(0.7, 0.6, 0.5), 0.0),
     ));
+
+    CameraSettings::default()
+}
+
+/// Cornell box lit only by the ceiling light. The room is closed behind the camera so no
+/// light from the outside can get in.
+pub fn cornell_box(scene: &mut Scene, _rng: &mut Rng) -> CameraSettings {
+    let red   = Lambertian::new_solid(Vec3::new(0.65, 0.05, 0.05));
+    let white = Lambertian::new_solid(Vec3::new(0.73, 0.73, 0.73));
+    let green = Lambertian::new_solid(Vec3::new(0.12, 0.45, 0.15));
+    let light = DiffuseLight::new(Vec3::fill(15.0));
+
+    scene.set_environment(ConstantEnvironment::new(Vec3::zero()));
+
+    let size  = 555.0;
+    let front = -810.0;
+    let depth = size - front;
+
+    let x = Vec3::new(size, 0.0, 0.0);
+    let y = Vec3::new(0.0, size, 0.0);
+    let z = Vec3::new(0.0, 0.0, depth);
+
+    let origin = Vec3::new(0.0, 0.0, front);
+
+    scene.add(Quad::new(origin + x, y, z, &green));
+    scene.add(Quad::new(origin, z, y, &red));
+    scene.add(Quad::new(origin, x, z, &white));
+    scene.add(Quad::new(origin + y, z, x, &white));
+    scene.add(Quad::new(origin + z, y, x, &white));
+    scene.add(Quad::new(origin, x, y, &white));
+
+    scene.add(Quad::new(Vec3::new(213.0, 554.0, 227.0), Vec3::new(0.0, 0.0, 105.0),
+                        Vec3::new(130.0, 0.0, 0.0), &light));
+
+    // Boxes standing on the floor rotated around their corner at `offset`.
+    let add_box = |scene: &mut Scene, size: Vec3, angle: f32, offset: Vec3| {
+        let (sin, cos) = f32::to_radians(angle).sin_cos();
+        let (sx, _, sz) = (size * 0.5).extract();
+
+        let center = offset + Vec3::new(cos * sx + sin * sz, size.extract().1 * 0.5,
+                                        -sin * sx + cos * sz);
+
+        scene.add(Cuboid::oriented(center, size, Vec3::new(cos, 0.0, -sin),
+                                   Vec3::new(0.0, 1.0, 0.0), &white));
+    };
+
+    add_box(scene, Vec3::fill(165.0), -18.0, Vec3::new(130.0, 0.0, 65.0));
+    add_box(scene, Vec3::new(165.0, 330.0, 165.0), 15.0, Vec3::new(265.0, 0.0, 295.0));
+
+    CameraSettings {
+        eye:    Vec3::new(278.0, 278.0, -800.0),
+        target: Vec3::new(278.0, 278.0, 0.0),
+        up:     Vec3::new(0.0, 1.0, 0.0),
+        fov:    40.0,
+    }
 }
@@ -0,0 +1,1091 @@
+//! Loader for the text scene description format.
+//!
+//! A scene file is a list of blocks. Every block starts with its kind, optionally followed by
+//! a name which other blocks can use to refer to it, and contains `key = value` properties:
+//!
+//! ```text
+//! # Comments start with `#` and last until the end of the line.
+//! camera {
+//!     eye    = (12, 2, 3)
+//!     target = (0, 0, 0)
+//!     fov    = 20
+//! }
+//!
+//! texture earth {
+//!     type = picture
+//!     path = "earthmap.jpg"
+//! }
+//!
+//! material globe {
+//!     type    = lambertian
+//!     texture = earth
+//! }
+//!
+//! sphere {
+//!     center   = (0, 0, -1)
+//!     radius   = 0.5
+//!     material = globe
+//! }
+//! ```
+//!
+//! Besides `sphere`, single triangles can be declared with a `triangle` block which takes the
+//! `a`, `b` and `c` vertex positions in counter-clockwise order and a `material`.
+//!
+//! Flat shapes are declared with a `plane` block (a `point` and a `normal`, the plane is
+//! infinite), a `quad` block (a parallelogram with a `corner` and `u` and `v` edge vectors)
+//! and a `disc` block (`center`, `normal` and `radius`). A `box` block takes its `min` and
+//! `max` corners and an optional `rotation` in degrees around the Y axis through its center.
+//! All of them take a `material`.
+//!
+//! Meshes are imported from Wavefront OBJ files with a `mesh` block which takes the `path`
+//! of the file and optionally a `material` overriding materials from the MTL libraries.
+//! Meshes can be placed with optional `scale` (a number or a vector), `rotation` (degrees
+//! around the X, Y and Z axes, in this order) and `translation`. Emissive triangles of
+//! transformed meshes aren't sampled directly as lights.
+//!
+//! The `environment` block sets what rays leaving the scene see. Its `type` is `constant`
+//! (`color`), `gradient` (`bottom` and `top` colors) or `map`, an equirectangular image
+//! (`path`, optional `rotation` in degrees around the Y axis and `intensity`). When no
+//! environment is specified the default sky gradient is used.
+//!
+//! Material `type` is `lambertian` (`color` or `texture`), `metal` (`albedo` and optional
+//! `fuzziness`), `dielectric` (`ior`), `diffuse_light` (`emission` and optional `intensity`)
+//! or `rough_conductor`. Dielectrics can be tinted by an `absorption` color, the fraction of
+//! light passing through unit distance, with optional `density` scaling the absorption.
+//! Instead of `ior` they can disperse light, which is visible only in spectral renders, by
+//! taking Cauchy coefficients as a `cauchy = (A, B, C)` vector or Sellmeier coefficients as
+//! `sellmeier_b` and `sellmeier_c` vectors, with wavelengths in micrometres.
+//! Rough conductors take `roughness` between 0 and 1 and either a `metal` preset (`gold`,
+//! `copper`, `aluminium` or `silver`) or the complex index of refraction as `eta` and `k`
+//! colors. A `rough_dielectric` takes `ior` and either a `roughness` number or a
+//! `roughness_texture`, whose average color is the roughness.
+//!
+//! Values can be numbers, vectors `(x, y, z)`, quoted strings and identifiers. A number can
+//! be used where a color is expected and is then used for all three channels. Relative paths
+//! are resolved against the directory containing the scene file.
+
+use crate::Vec3;
+use crate::math::{CameraSettings, Mat4};
+use crate::traceable::{Sphere, TriangleMesh, Plane, Quad, Disc, Cuboid, Instance, DynTraceable};
+use crate::texture::{SharedTexture, SolidTexture, PictureTexture};
+use crate::environment::{ConstantEnvironment, GradientEnvironment, MapEnvironment};
+use crate::material::{SharedMaterial, Lambertian, Metal, Dielectric, DiffuseLight};
+use crate::material::{RoughConductor, Conductor, RoughDielectric, Ior};
+use super::{Scene, Blas, BvhSettings};
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::cell::Cell;
+use std::sync::Arc;
+use std::fmt;
+use std::fs;
+use std::io;
+
+pub struct LoadedScene {
+    pub scene:  Scene,
+    pub camera: CameraSettings,
+    /// Meshes, MTL libraries, textures and environment maps read while loading the scene,
+    /// in the order they were read. The scene file itself isn't included.
+    pub files:  Vec<PathBuf>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Position {
+    pub line:   usize,
+    pub column: usize,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(PathBuf, io::Error),
+    Syntax(PathBuf, Position, String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(path, err) => {
+                write!(f, "{}: {}", path.display(), err)
+            }
+            LoadError::Syntax(path, position, message) => {
+                write!(f, "{}:{}:{}: {}", path.display(), position.line, position.column,
+                       message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+type ParseResult<T> = Result<T, (Position, String)>;
+
+#[derive(Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    Number(f32),
+    String(String),
+    LeftBrace,
+    RightBrace,
+    LeftParen,
+    RightParen,
+    Comma,
+    Equals,
+    Eof,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Identifier(name) => write!(f, "identifier `{}`", name),
+            Token::Number(value)    => write!(f, "number `{}`", value),
+            Token::String(value)    => write!(f, "string \"{}\"", value),
+            Token::LeftBrace        => write!(f, "`{{`"),
+            Token::RightBrace       => write!(f, "`}}`"),
+            Token::LeftParen        => write!(f, "`(`"),
+            Token::RightParen       => write!(f, "`)`"),
+            Token::Comma            => write!(f, "`,`"),
+            Token::Equals           => write!(f, "`=`"),
+            Token::Eof              => write!(f, "end of file"),
+        }
+    }
+}
+
+struct Lexer<'a> {
+    chars:    std::iter::Peekable<std::str::Chars<'a>>,
+    position: Position,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            chars:    source.chars().peekable(),
+            position: Position { line: 1, column: 1 },
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+
+        if c == '\n' {
+            self.position.line  += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c == '#' {
+                while let Some(c) = self.bump() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            } else if c.is_whitespace() {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> ParseResult<(Position, Token)> {
+        self.skip_whitespace();
+
+        let start = self.position;
+
+        let c = match self.chars.peek() {
+            Some(&c) => c,
+            None     => return Ok((start, Token::Eof)),
+        };
+
+        let simple = match c {
+            '{' => Some(Token::LeftBrace),
+            '}' => Some(Token::RightBrace),
+            '(' => Some(Token::LeftParen),
+            ')' => Some(Token::RightParen),
+            ',' => Some(Token::Comma),
+            '=' => Some(Token::Equals),
+            _   => None,
+        };
+
+        if let Some(token) = simple {
+            self.bump();
+
+            return Ok((start, token));
+        }
+
+        if c == '"' {
+            self.bump();
+
+            let mut string = String::new();
+
+            loop {
+                match self.bump() {
+                    Some('"')  => break,
+                    Some('\n') | None => {
+                        return Err((start, String::from("unterminated string")));
+                    }
+                    Some(c) => string.push(c),
+                }
+            }
+
+            return Ok((start, Token::String(string)));
+        }
+
+        if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' {
+            let mut number = String::new();
+
+            while let Some(&c) = self.chars.peek() {
+                let exponent_sign = (c == '-' || c == '+') &&
+                    number.ends_with(['e', 'E']);
+
+                if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exponent_sign ||
+                    (number.is_empty() && (c == '-' || c == '+')) {
+                    number.push(c);
+                    self.bump();
+                } else {
+                    break;
+                }
+            }
+
+            return match number.parse::<f32>() {
+                Ok(value) if value.is_finite() => Ok((start, Token::Number(value))),
+                _ => Err((start, format!("invalid number `{}`", number))),
+            };
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let mut identifier = String::new();
+
+            while let Some(&c) = self.chars.peek() {
+                if c.is_alphanumeric() || c == '_' {
+                    identifier.push(c);
+                    self.bump();
+                } else {
+                    break;
+                }
+            }
+
+            return Ok((start, Token::Identifier(identifier)));
+        }
+
+        Err((start, format!("unexpected character `{}`", c)))
+    }
+}
+
+enum Value {
+    Number(f32),
+    Vector(Vec3),
+    String(String),
+    Identifier(String),
+}
+
+impl Value {
+    fn description(&self) -> &'static str {
+        match self {
+            Value::Number(..)     => "a number",
+            Value::Vector(..)     => "a vector",
+            Value::String(..)     => "a string",
+            Value::Identifier(..) => "an identifier",
+        }
+    }
+}
+
+struct Property {
+    key:      String,
+    value:    Value,
+    position: Position,
+    used:     Cell<bool>,
+}
+
+struct Block {
+    kind:       String,
+    name:       Option<(Position, String)>,
+    properties: Vec<Property>,
+    position:   Position,
+}
+
+struct Parser<'a> {
+    lexer:     Lexer<'a>,
+    lookahead: (Position, Token),
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> ParseResult<Self> {
+        let mut lexer = Lexer::new(source);
+        let lookahead = lexer.next_token()?;
+
+        Ok(Self {
+            lexer,
+            lookahead,
+        })
+    }
+
+    fn next(&mut self) -> ParseResult<(Position, Token)> {
+        let next = self.lexer.next_token()?;
+
+        Ok(std::mem::replace(&mut self.lookahead, next))
+    }
+
+    fn expect(&mut self, expected: Token) -> ParseResult<Position> {
+        let (position, token) = self.next()?;
+
+        if token == expected {
+            Ok(position)
+        } else {
+            Err((position, format!("expected {}, found {}", expected, token)))
+        }
+    }
+
+    fn identifier(&mut self, what: &str) -> ParseResult<(Position, String)> {
+        match self.next()? {
+            (position, Token::Identifier(name)) => Ok((position, name)),
+            (position, token) => Err((position, format!("expected {}, found {}", what, token))),
+        }
+    }
+
+    fn number(&mut self) -> ParseResult<f32> {
+        match self.next()? {
+            (_, Token::Number(value)) => Ok(value),
+            (position, token) => Err((position, format!("expected number, found {}", token))),
+        }
+    }
+
+    fn value(&mut self) -> ParseResult<Value> {
+        match self.next()? {
+            (_, Token::Number(value))     => Ok(Value::Number(value)),
+            (_, Token::String(value))     => Ok(Value::String(value)),
+            (_, Token::Identifier(value)) => Ok(Value::Identifier(value)),
+            (_, Token::LeftParen)         => {
+                let x = self.number()?;
+                self.expect(Token::Comma)?;
+                let y = self.number()?;
+                self.expect(Token::Comma)?;
+                let z = self.number()?;
+                self.expect(Token::RightParen)?;
+
+                Ok(Value::Vector(Vec3::new(x, y, z)))
+            }
+            (position, token) => Err((position, format!("expected value, found {}", token))),
+        }
+    }
+
+    fn block(&mut self) -> ParseResult<Block> {
+        let (position, kind) = self.identifier("block kind")?;
+
+        let name = match &self.lookahead.1 {
+            Token::Identifier(..) => Some(self.identifier("block name")?),
+            _                     => None,
+        };
+
+        self.expect(Token::LeftBrace)?;
+
+        let mut properties: Vec<Property> = Vec::new();
+
+        while self.lookahead.1 != Token::RightBrace {
+            let (position, key) = self.identifier("property name or `}`")?;
+
+            if properties.iter().any(|property| property.key == key) {
+                return Err((position, format!("duplicate property `{}`", key)));
+            }
+
+            self.expect(Token::Equals)?;
+
+            let value = self.value()?;
+
+            properties.push(Property {
+                key,
+                value,
+                position,
+                used: Cell::new(false),
+            });
+        }
+
+        self.expect(Token::RightBrace)?;
+
+        Ok(Block {
+            kind,
+            name,
+            properties,
+            position,
+        })
+    }
+
+    fn blocks(&mut self) -> ParseResult<Vec<Block>> {
+        let mut blocks = Vec::new();
+
+        while self.lookahead.1 != Token::Eof {
+            blocks.push(self.block()?);
+        }
+
+        Ok(blocks)
+    }
+}
+
+impl Block {
+    fn property(&self, key: &str) -> Option<&Property> {
+        let property = self.properties.iter().find(|property| property.key == key)?;
+
+        property.used.set(true);
+
+        Some(property)
+    }
+
+    fn required(&self, key: &str) -> ParseResult<&Property> {
+        self.property(key).ok_or_else(|| {
+            (self.position, format!("`{}` is missing required property `{}`", self.kind, key))
+        })
+    }
+
+    fn mismatch<T>(property: &Property, expected: &str) -> ParseResult<T> {
+        Err((property.position, format!("`{}` must be {}, found {}", property.key, expected,
+                                        property.value.description())))
+    }
+
+    fn number(&self, key: &str) -> ParseResult<f32> {
+        let property = self.required(key)?;
+
+        match property.value {
+            Value::Number(value) => Ok(value),
+            _                    => Self::mismatch(property, "a number"),
+        }
+    }
+
+    fn number_or(&self, key: &str, default: f32) -> ParseResult<f32> {
+        match self.property(key) {
+            Some(..) => self.number(key),
+            None     => Ok(default),
+        }
+    }
+
+    fn vector(&self, key: &str) -> ParseResult<Vec3> {
+        let property = self.required(key)?;
+
+        match property.value {
+            Value::Vector(value) => Ok(value),
+            _                    => Self::mismatch(property, "a vector"),
+        }
+    }
+
+    fn vector_or(&self, key: &str, default: Vec3) -> ParseResult<Vec3> {
+        match self.property(key) {
+            Some(..) => self.vector(key),
+            None     => Ok(default),
+        }
+    }
+
+    /// Reads vector which can be also given as a single number used for all components.
+    fn broadcast(&self, key: &str, expected: &str) -> ParseResult<Vec3> {
+        let property = self.required(key)?;
+
+        match property.value {
+            Value::Vector(value) => Ok(value),
+            Value::Number(value) => Ok(Vec3::fill(value)),
+            _                    => Self::mismatch(property, expected),
+        }
+    }
+
+    fn color(&self, key: &str) -> ParseResult<Vec3> {
+        self.broadcast(key, "a color")
+    }
+
+    fn string(&self, key: &str) -> ParseResult<&str> {
+        let property = self.required(key)?;
+
+        match &property.value {
+            Value::String(value) => Ok(value),
+            _                    => Self::mismatch(property, "a string"),
+        }
+    }
+
+    fn identifier(&self, key: &str) -> ParseResult<(Position, &str)> {
+        let property = self.required(key)?;
+
+        match &property.value {
+            Value::Identifier(value) => Ok((property.position, value)),
+            _                        => Self::mismatch(property, "an identifier"),
+        }
+    }
+
+    fn positive(&self, key: &str, value: f32) -> ParseResult<f32> {
+        if value > 0.0 {
+            Ok(value)
+        } else {
+            let position = self.property(key).map_or(self.position, |p| p.position);
+
+            Err((position, format!("`{}` must be greater than zero", key)))
+        }
+    }
+
+    fn unit(&self, key: &str, value: f32) -> ParseResult<f32> {
+        if (0.0..=1.0).contains(&value) {
+            Ok(value)
+        } else {
+            let position = self.property(key).map_or(self.position, |p| p.position);
+
+            Err((position, format!("`{}` must be between 0 and 1", key)))
+        }
+    }
+
+    fn unit_color(&self, key: &str, value: Vec3) -> ParseResult<Vec3> {
+        let (r, g, b) = value.extract();
+
+        if [r, g, b].iter().all(|c| (0.0..=1.0).contains(c)) {
+            Ok(value)
+        } else {
+            let position = self.property(key).map_or(self.position, |p| p.position);
+
+            Err((position, format!("`{}` components must be between 0 and 1", key)))
+        }
+    }
+
+    fn nonzero(&self, key: &str, value: Vec3) -> ParseResult<Vec3> {
+        if value.length_sqr() > 0.0 {
+            Ok(value)
+        } else {
+            let position = self.property(key).map_or(self.position, |p| p.position);
+
+            Err((position, format!("`{}` cannot be a zero vector", key)))
+        }
+    }
+
+    fn finish(&self) -> ParseResult<()> {
+        match self.properties.iter().find(|property| !property.used.get()) {
+            Some(property) => Err((property.position, format!("unknown property `{}` for `{}`",
+                                                              property.key, self.kind))),
+            None => Ok(()),
+        }
+    }
+}
+
+struct Builder<'a> {
+    path:      &'a Path,
+    directory: &'a Path,
+    scene:     Scene,
+    camera:    Option<CameraSettings>,
+    has_env:   bool,
+    textures:  HashMap<String, SharedTexture>,
+    materials: HashMap<String, SharedMaterial>,
+    /// BLASes of transformed meshes keyed by their file and material override.
+    blases:    HashMap<(PathBuf, Option<String>), Arc<DynTraceable>>,
+    files:     Vec<PathBuf>,
+}
+
+impl<'a> Builder<'a> {
+    fn name<'b>(&self, block: &'b Block) -> ParseResult<(Position, &'b str)> {
+        match &block.name {
+            Some((position, name)) => Ok((*position, name)),
+            None => Err((block.position, format!("`{}` must be named", block.kind))),
+        }
+    }
+
+    fn unnamed(&self, block: &Block) -> ParseResult<()> {
+        match &block.name {
+            Some((position, _)) => Err((*position, format!("`{}` cannot be named",
+                                                           block.kind))),
+            None => Ok(()),
+        }
+    }
+
+    fn lookup<'b, T>(map: &'b HashMap<String, T>, what: &str,
+                     (position, name): (Position, &str)) -> ParseResult<&'b T> {
+        map.get(name).ok_or_else(|| (position, format!("unknown {} `{}`", what, name)))
+    }
+
+    fn camera(&mut self, block: &Block) -> ParseResult<()> {
+        self.unnamed(block)?;
+
+        if self.camera.is_some() {
+            return Err((block.position, String::from("camera is already defined")));
+        }
+
+        let defaults = CameraSettings::default();
+
+        let fov = block.number_or("fov", defaults.fov)?;
+
+        if !(fov > 0.0 && fov < 180.0) {
+            let position = block.property("fov").unwrap().position;
+
+            return Err((position, String::from("`fov` must be between 0 and 180 degrees")));
+        }
+
+        self.camera = Some(CameraSettings {
+            eye:    block.vector_or("eye",    defaults.eye)?,
+            target: block.vector_or("target", defaults.target)?,
+            up:     block.vector_or("up",     defaults.up)?,
+            fov,
+        });
+
+        Ok(())
+    }
+
+    fn environment(&mut self, block: &Block) -> ParseResult<()> {
+        self.unnamed(block)?;
+
+        if self.has_env {
+            return Err((block.position, String::from("environment is already defined")));
+        }
+
+        let (type_position, kind) = block.identifier("type")?;
+
+        let environment = match kind {
+            "constant" => ConstantEnvironment::new(block.color("color")?),
+            "gradient" => GradientEnvironment::new(block.color("bottom")?, block.color("top")?),
+            "map"      => {
+                let path      = self.directory.join(block.string("path")?);
+                let rotation  = block.number_or("rotation", 0.0)?;
+                let intensity = block.number_or("intensity", 1.0)?;
+
+                let environment = MapEnvironment::open(&path, rotation, intensity)
+                    .map_err(|err| {
+                        let position = block.property("path").unwrap().position;
+
+                        (position, format!("failed to open `{}`: {}", path.display(), err))
+                    })?;
+
+                self.files.push(path);
+                environment
+            }
+            _ => return Err((type_position, format!("unknown environment type `{}`", kind))),
+        };
+
+        self.scene.set_environment(environment);
+        self.has_env = true;
+
+        Ok(())
+    }
+
+    fn texture(&mut self, block: &Block) -> ParseResult<()> {
+        let (position, name) = self.name(block)?;
+        let (type_position, kind) = block.identifier("type")?;
+
+        let texture = match kind {
+            "solid"   => SolidTexture::new(block.color("color")?),
+            "picture" => {
+                let path = self.directory.join(block.string("path")?);
+
+                let texture = PictureTexture::open(&path).map_err(|err| {
+                    let position = block.property("path").unwrap().position;
+
+                    (position, format!("failed to open `{}`: {}", path.display(), err))
+                })?;
+
+                self.files.push(path);
+                texture
+            }
+            _ => return Err((type_position, format!("unknown texture type `{}`", kind))),
+        };
+
+        if self.textures.insert(name.to_string(), texture).is_some() {
+            return Err((position, format!("texture `{}` is already defined", name)));
+        }
+
+        Ok(())
+    }
+
+    fn material(&mut self, block: &Block) -> ParseResult<()> {
+        let (position, name) = self.name(block)?;
+        let (type_position, kind) = block.identifier("type")?;
+
+        let material = match kind {
+            "lambertian" => {
+                match (block.property("texture"), block.property("color")) {
+                    (Some(..), Some(property)) => {
+                        return Err((property.position,
+                                    String::from("`color` cannot be used with `texture`")));
+                    }
+                    (Some(..), None) => {
+                        let texture = block.identifier("texture")?;
+
+                        Lambertian::new(Self::lookup(&self.textures, "texture", texture)?
+                                        .clone())
+                    }
+                    (None, _) => Lambertian::new_solid(block.color("color")?),
+                }
+            }
+            "metal" => {
+                let fuzziness = block.number_or("fuzziness", 0.0)?;
+
+                Metal::new(block.color("albedo")?, fuzziness)
+            }
+            "rough_conductor" => {
+                let roughness = block.number("roughness")?;
+                let roughness = block.unit("roughness", roughness)?;
+
+                match (block.property("metal"), block.property("eta")) {
+                    (Some(..), Some(property)) => {
+                        return Err((property.position,
+                                    String::from("`eta` cannot be used with `metal`")));
+                    }
+                    (Some(..), None) => {
+                        let (position, metal) = block.identifier("metal")?;
+
+                        let conductor = match metal {
+                            "gold"      => Conductor::Gold,
+                            "copper"    => Conductor::Copper,
+                            "aluminium" => Conductor::Aluminium,
+                            "silver"    => Conductor::Silver,
+                            _ => return Err((position, format!("unknown metal `{}`", metal))),
+                        };
+
+                        RoughConductor::preset(conductor, roughness)
+                    }
+                    (None, _) => {
+                        RoughConductor::new(block.color("eta")?, block.color("k")?, roughness)
+                    }
+                }
+            }
+            "rough_dielectric" => {
+                let ior = block.number("ior")?;
+                let ior = block.positive("ior", ior)?;
+
+                match (block.property("roughness_texture"), block.property("roughness")) {
+                    (Some(..), Some(property)) => {
+                        return Err((property.position, String::from(
+                                    "`roughness` cannot be used with `roughness_texture`")));
+                    }
+                    (Some(..), None) => {
+                        let texture = block.identifier("roughness_texture")?;
+
+                        RoughDielectric::new(ior, Self::lookup(&self.textures, "texture",
+                                                               texture)?.clone())
+                    }
+                    (None, _) => {
+                        let roughness = block.number("roughness")?;
+
+                        RoughDielectric::new_solid(ior, block.unit("roughness", roughness)?)
+                    }
+                }
+            }
+            "dielectric" => {
+                let ior = self.ior(block)?;
+
+                match block.property("absorption") {
+                    Some(..) => {
+                        let color   = block.color("absorption")?;
+                        let density = block.number_or("density", 1.0)?;
+
+                        Dielectric::with_absorption(ior, block.unit_color("absorption", color)?,
+                                                    block.positive("density", density)?)
+                    }
+                    None => Dielectric::new(ior),
+                }
+            }
+            "diffuse_light" => {
+                let intensity = block.number_or("intensity", 1.0)?;
+
+                DiffuseLight::new(block.color("emission")? * intensity)
+            }
+            _ => return Err((type_position, format!("unknown material type `{}`", kind))),
+        };
+
+        if self.materials.insert(name.to_string(), material).is_some() {
+            return Err((position, format!("material `{}` is already defined", name)));
+        }
+
+        Ok(())
+    }
+
+    /// Reads index of refraction of a dielectric, which is either a constant `ior` or given
+    /// by `cauchy` or `sellmeier_b` and `sellmeier_c` coefficients.
+    fn ior(&self, block: &Block) -> ParseResult<Ior> {
+        let given: Vec<&str> = ["ior", "cauchy", "sellmeier_b"].iter()
+            .filter(|key| block.property(key).is_some())
+            .copied()
+            .collect();
+
+        if let Some(key) = given.get(1) {
+            return Err((block.required(key)?.position, format!(
+                        "`{}` cannot be used with `{}`", key, given[0])));
+        }
+
+        let (key, ior) = match given.first() {
+            Some(&"cauchy") => ("cauchy", Ior::Cauchy(block.vector("cauchy")?.extract_array())),
+            Some(&"sellmeier_b") => {
+                ("sellmeier_b", Ior::Sellmeier(block.vector("sellmeier_b")?.extract_array(),
+                                               block.vector("sellmeier_c")?.extract_array()))
+            }
+            _ => {
+                let ior = block.number("ior")?;
+
+                ("ior", Ior::Constant(block.positive("ior", ior)?))
+            }
+        };
+
+        if !ior.is_valid() {
+            return Err((block.required(key)?.position, format!(
+                        "`{}` must give a positive finite index of refraction over the visible \
+                         spectrum", key)));
+        }
+
+        Ok(ior)
+    }
+
+    fn sphere(&mut self, block: &Block) -> ParseResult<()> {
+        self.unnamed(block)?;
+
+        let radius   = block.number("radius")?;
+        let material = block.identifier("material")?;
+        let material = Self::lookup(&self.materials, "material", material)?;
+
+        self.scene.add(Sphere::new(block.vector("center")?, block.positive("radius", radius)?,
+                                   material));
+
+        Ok(())
+    }
+
+    fn triangle(&mut self, block: &Block) -> ParseResult<()> {
+        self.unnamed(block)?;
+
+        let material  = block.identifier("material")?;
+        let material  = Self::lookup(&self.materials, "material", material)?;
+        let positions = vec![block.vector("a")?, block.vector("b")?, block.vector("c")?];
+
+        let mesh = TriangleMesh::new(positions, None, None, vec![[0, 1, 2]], material);
+
+        self.scene.add_mesh(&mesh);
+
+        Ok(())
+    }
+
+    fn plane(&mut self, block: &Block) -> ParseResult<()> {
+        self.unnamed(block)?;
+
+        let normal   = block.vector("normal")?;
+        let material = block.identifier("material")?;
+        let material = Self::lookup(&self.materials, "material", material)?;
+
+        self.scene.add(Plane::new(block.vector("point")?, block.nonzero("normal", normal)?,
+                                  material));
+
+        Ok(())
+    }
+
+    fn quad(&mut self, block: &Block) -> ParseResult<()> {
+        self.unnamed(block)?;
+
+        let (u, v)   = (block.vector("u")?, block.vector("v")?);
+        let material = block.identifier("material")?;
+        let material = Self::lookup(&self.materials, "material", material)?;
+
+        if Vec3::cross(u, v).length_sqr() == 0.0 {
+            return Err((block.position, String::from("`u` and `v` cannot be parallel")));
+        }
+
+        self.scene.add(Quad::new(block.vector("corner")?, u, v, material));
+
+        Ok(())
+    }
+
+    fn disc(&mut self, block: &Block) -> ParseResult<()> {
+        self.unnamed(block)?;
+
+        let normal   = block.vector("normal")?;
+        let radius   = block.number("radius")?;
+        let material = block.identifier("material")?;
+        let material = Self::lookup(&self.materials, "material", material)?;
+
+        self.scene.add(Disc::new(block.vector("center")?, block.nonzero("normal", normal)?,
+                                 block.positive("radius", radius)?, material));
+
+        Ok(())
+    }
+
+    fn cuboid(&mut self, block: &Block) -> ParseResult<()> {
+        self.unnamed(block)?;
+
+        let (min, max) = (block.vector("min")?, block.vector("max")?);
+        let rotation   = block.number_or("rotation", 0.0)?;
+        let material   = block.identifier("material")?;
+        let material   = Self::lookup(&self.materials, "material", material)?;
+
+        let size = max - min;
+        let (sx, sy, sz) = size.extract();
+
+        if !(sx > 0.0 && sy > 0.0 && sz > 0.0) {
+            let position = block.property("max").unwrap().position;
+
+            return Err((position, String::from("`max` must be greater than `min` on all axes")));
+        }
+
+        let (sin, cos) = rotation.to_radians().sin_cos();
+
+        self.scene.add(Cuboid::oriented((min + max) * 0.5, size, Vec3::new(cos, 0.0, -sin),
+                                        Vec3::new(0.0, 1.0, 0.0), material));
+
+        Ok(())
+    }
+
+    /// Reads optional `scale`, `rotation` and `translation` of an object, which are applied
+    /// in this order. Returns `None` if none of them is present.
+    fn transform(block: &Block) -> ParseResult<Option<Mat4>> {
+        if ["scale", "rotation", "translation"].iter().all(|key| block.property(key).is_none()) {
+            return Ok(None);
+        }
+
+        let scale = match block.property("scale") {
+            Some(..) => block.broadcast("scale", "a number or a vector")?,
+            None     => Vec3::fill(1.0),
+        };
+
+        let (x, y, z) = block.vector_or("rotation", Vec3::zero())?.extract();
+
+        let rotation = Mat4::rotation(Vec3::new(0.0, 0.0, 1.0), z.to_radians()) *
+            Mat4::rotation(Vec3::new(0.0, 1.0, 0.0), y.to_radians()) *
+            Mat4::rotation(Vec3::new(1.0, 0.0, 0.0), x.to_radians());
+
+        let translation = block.vector_or("translation", Vec3::zero())?;
+        let transform   = Mat4::translation(translation) * rotation * Mat4::scale(scale);
+
+        if transform.inverse().is_none() {
+            let position = block.property("scale").map_or(block.position, |p| p.position);
+
+            return Err((position, String::from("`scale` cannot be zero")));
+        }
+
+        Ok(Some(transform))
+    }
+
+    fn mesh(&mut self, block: &Block) -> Result<(), LoadError> {
+        let path   = self.path;
+        let syntax = |(position, message)| LoadError::Syntax(path.to_path_buf(), position,
+                                                            message);
+
+        self.unnamed(block).map_err(syntax)?;
+
+        let material = match block.property("material") {
+            Some(..) => Some(block.identifier("material").map_err(syntax)?),
+            None     => None,
+        };
+
+        let shared = match material {
+            Some(material) => {
+                Some(Self::lookup(&self.materials, "material", material).map_err(syntax)?)
+            }
+            None => None,
+        };
+
+        let path      = self.directory.join(block.string("path").map_err(syntax)?);
+        let transform = match Self::transform(block).map_err(syntax)? {
+            Some(transform) => transform,
+            None            => {
+                for mesh in super::obj::load_with_files(&path, shared, &mut self.files)? {
+                    self.scene.add_mesh(&mesh);
+                }
+
+                return Ok(());
+            }
+        };
+
+        // Transformed meshes are instances of a BLAS shared by all blocks using the same file
+        // and material.
+        let key  = (path, material.map(|(_, name)| name.to_string()));
+        let blas = match self.blases.get(&key) {
+            Some(blas) => blas.clone(),
+            None       => {
+                let meshes = super::obj::load_with_files(&key.0, shared, &mut self.files)?;
+
+                if meshes.is_empty() {
+                    return Ok(());
+                }
+
+                let settings = self.scene.bvh_settings();
+                let blas: Arc<DynTraceable> = Arc::new(Blas::from_meshes(&meshes, &settings));
+
+                self.blases.insert(key, blas.clone());
+                blas
+            }
+        };
+
+        self.scene.add_instance(Instance::new(blas, transform));
+
+        Ok(())
+    }
+
+    fn build(&mut self, blocks: &[Block]) -> Result<(), LoadError> {
+        let path   = self.path;
+        let syntax = |(position, message)| LoadError::Syntax(path.to_path_buf(), position,
+                                                            message);
+
+        for block in blocks {
+            match block.kind.as_str() {
+                "camera"      => self.camera(block).map_err(syntax)?,
+                "environment" => self.environment(block).map_err(syntax)?,
+                "texture"     => self.texture(block).map_err(syntax)?,
+                "material"    => self.material(block).map_err(syntax)?,
+                "sphere"      => self.sphere(block).map_err(syntax)?,
+                "triangle"    => self.triangle(block).map_err(syntax)?,
+                "plane"       => self.plane(block).map_err(syntax)?,
+                "quad"        => self.quad(block).map_err(syntax)?,
+                "disc"        => self.disc(block).map_err(syntax)?,
+                "box"         => self.cuboid(block).map_err(syntax)?,
+                "mesh"        => self.mesh(block)?,
+                kind          => {
+                    return Err(syntax((block.position, format!("unknown block kind `{}`",
+                                                               kind))));
+                }
+            }
+
+            block.finish().map_err(syntax)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses scene description from `source`. `path` is used for error reporting and resolving
+/// relative paths of referenced files.
+pub fn parse(source: &str, path: &Path) -> Result<LoadedScene, LoadError> {
+    parse_with_settings(source, path, BvhSettings::default())
+}
+
+/// Like `parse`, but BLASes of meshes and the returned scene use BVH `settings`.
+pub fn parse_with_settings(source: &str, path: &Path, settings: BvhSettings)
+    -> Result<LoadedScene, LoadError>
+{
+    let blocks = Parser::new(source)
+        .and_then(|mut parser| parser.blocks())
+        .map_err(|(position, message)| LoadError::Syntax(path.to_path_buf(), position,
+                                                        message))?;
+
+    let mut builder = Builder {
+        path,
+        directory: path.parent().unwrap_or_else(|| Path::new("")),
+        scene:     Scene::new(),
+        camera:    None,
+        has_env:   false,
+        textures:  HashMap::new(),
+        materials: HashMap::new(),
+        blases:    HashMap::new(),
+        files:     Vec::new(),
+    };
+
+    builder.scene.set_bvh_settings(settings);
+    builder.build(&blocks)?;
+
+    Ok(LoadedScene {
+        scene:  builder.scene,
+        camera: builder.camera.unwrap_or_default(),
+        files:  builder.files,
+    })
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<LoadedScene, LoadError> {
+    load_with_settings(path, BvhSettings::default())
+}
+
+/// Like `load`, but BLASes of meshes and the returned scene use BVH `settings`.
+pub fn load_with_settings(path: impl AsRef<Path>, settings: BvhSettings)
+    -> Result<LoadedScene, LoadError>
+{
+    let path = path.as_ref();
+
+    let source = fs::read_to_string(path)
+        .map_err(|err| LoadError::Io(path.to_path_buf(), err))?;
+
+    parse_with_settings(&source, path, settings)
+}
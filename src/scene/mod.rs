@@ -1,61 +1,232 @@
+//! Scene containing all objects to render and their acceleration structures.
+//!
+//! Objects added directly are traced through one BVH built by `Scene::construct_bvh`.
+//! Instances of shared geometry (usually a `Blas` over mesh triangles) have their own top
+//! level BVH, which can be rebuilt cheaply after moving them, e.g. between animation frames.
+
 pub mod generators;
+pub mod loader;
+pub mod obj;
 mod bvh;
 
-use crate::{Vec3, Ray};
-use crate::traceable::{HitRecord, Traceable, DynTraceable};
-use bvh::BvhNode;
+use crate::Ray;
+use crate::traceable::{HitRecord, Traceable, DynTraceable, TriangleMesh, Instance};
+use crate::math::Mat4;
+use crate::rng::Rng;
+use crate::environment::{Environment, SharedEnvironment, GradientEnvironment};
+
+use bvh::Bvh;
+
+use std::sync::Arc;
+
+pub use bvh::{Blas, BvhSettings, BvhWidth};
 
-use std::time::Instant;
-use std::io::{self, Write};
+/// Intersections closer than this to the ray origin are ignored, so that rays leaving a
+/// surface don't hit it again due to rounding errors.
+const T_MIN: f32 = 0.001;
 
+/// Handle of an instance added to the scene.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct InstanceId(usize);
+
+/// Collection of objects together with the environment surrounding them. Objects can be
+/// traced only after `construct_bvh` is called.
 pub struct Scene {
-    objects:  Vec<Box<DynTraceable>>,
-    bvh_root: Option<BvhNode>,
+    /// Objects added since the last `construct_bvh` call.
+    objects:     Vec<Arc<DynTraceable>>,
+    /// Objects in `bvh`, kept so that it can be rebuilt when more objects are added.
+    bounded:     Vec<Arc<DynTraceable>>,
+    /// Objects without a finite bounding box which are traced outside of the BVH.
+    unbounded:   Vec<Arc<DynTraceable>>,
+    instances:   Vec<Instance>,
+    lights:      Vec<Arc<DynTraceable>>,
+    bvh:         Option<Bvh>,
+    /// Top level BVH over copies of `instances` made when it was last built.
+    tlas:        Option<Bvh>,
+    environment: SharedEnvironment,
+    settings:    BvhSettings,
 }
 
 impl Scene {
+    /// Creates empty scene lit by the sky.
     pub fn new() -> Self {
         Self {
-            objects:  Vec::new(),
-            bvh_root: None,
+            objects:     Vec::new(),
+            bounded:     Vec::new(),
+            unbounded:   Vec::new(),
+            instances:   Vec::new(),
+            lights:      Vec::new(),
+            bvh:         None,
+            tlas:        None,
+            environment: GradientEnvironment::sky(),
+            settings:    BvhSettings::default(),
         }
     }
 
-    pub fn trace(&self, ray: &Ray) -> Option<HitRecord> {
-        const T_MIN: f32 = 0.001;
+    pub fn environment(&self) -> &dyn Environment {
+        &*self.environment
+    }
+
+    pub fn set_environment(&mut self, environment: SharedEnvironment) {
+        self.environment = environment;
+    }
+
+    /// Sets parameters used by `construct_bvh` and `rebuild_tlas`.
+    pub fn set_bvh_settings(&mut self, settings: BvhSettings) {
+        self.settings = settings;
+    }
+
+    pub fn bvh_settings(&self) -> BvhSettings {
+        self.settings
+    }
+
+    /// Finds the closest intersection along `ray`.
+    pub fn trace(&self, ray: &Ray) -> Option<HitRecord<'_>> {
+        self.trace_until(ray, f32::MAX)
+    }
 
-        let mut closest_distance = f32::MAX;
+    /// Finds the closest intersection which is nearer than `max_t`.
+    pub fn trace_until(&self, ray: &Ray, max_t: f32) -> Option<HitRecord<'_>> {
+        let mut closest_distance = max_t;
         let mut closest_record   = None;
 
-        if let Some(bvh_root) = self.bvh_root.as_ref() {
-            let inv_direction = Vec3::fill(1.0) / ray.direction;
+        if let Some(bvh) = self.bvh.as_ref() {
+            closest_record = bvh.trace(ray, T_MIN, closest_distance);
 
-            bvh_root.trace(ray, inv_direction, T_MIN, closest_distance)
-        } else {
-            for object in &self.objects {
-                if let Some(record) = object.trace(ray, T_MIN, closest_distance) {
-                    closest_distance = record.t;
-                    closest_record   = Some(record);
-                }
+            if let Some(record) = &closest_record {
+                closest_distance = record.t;
             }
-            
-            closest_record
         }
+
+        if let Some(tlas) = self.tlas.as_ref() {
+            if let Some(record) = tlas.trace(ray, T_MIN, closest_distance) {
+                closest_distance = record.t;
+                closest_record   = Some(record);
+            }
+        }
+
+        // Before the BVH is constructed all objects are traced here.
+        for object in self.objects.iter().chain(&self.unbounded) {
+            if let Some(record) = object.trace(ray, T_MIN, closest_distance) {
+                closest_distance = record.t;
+                closest_record   = Some(record);
+            }
+        }
+
+        closest_record
     }
 
+    /// Returns true if anything intersects `ray` nearer than `max_t`. Cheaper than
+    /// `trace_until` as the search stops at the first intersection found.
+    pub fn occluded(&self, ray: &Ray, max_t: f32) -> bool {
+        // Unbounded objects are few and cheap to test, so they go first.
+        let occluded = |object: &Arc<DynTraceable>| object.occluded(ray, T_MIN, max_t);
+
+        self.unbounded.iter().any(occluded) ||
+            self.objects.iter().any(occluded) ||
+            self.bvh.as_ref().is_some_and(|bvh| bvh.occluded(ray, T_MIN, max_t)) ||
+            self.tlas.as_ref().is_some_and(|tlas| tlas.occluded(ray, T_MIN, max_t))
+    }
+
+    /// Picks one of the scene lights uniformly. Returns the light together with probability
+    /// of picking it.
+    pub fn sample_light(&self, rng: &mut Rng) -> Option<(&DynTraceable, f32)> {
+        if self.lights.is_empty() {
+            return None;
+        }
+
+        let count = self.lights.len();
+        let index = ((rng.rand::<f32>() * count as f32) as usize).min(count - 1);
+
+        Some((&*self.lights[index], self.light_selection_pdf()))
+    }
+
+    /// Number of lights sampled directly, known after `construct_bvh` is called.
+    pub fn light_count(&self) -> usize {
+        self.lights.len()
+    }
+
+    /// Probability of `sample_light` picking any given light.
+    pub fn light_selection_pdf(&self) -> f32 {
+        1.0 / self.lights.len() as f32
+    }
+
+    /// Adds object to the scene.
     pub fn add(&mut self, object: impl Traceable + Send + Sync + 'static) {
-        self.objects.push(Box::new(object));
+        self.objects.push(Arc::new(object));
+    }
+
+    /// Adds all triangles of `mesh` to the scene.
+    pub fn add_mesh(&mut self, mesh: &Arc<TriangleMesh>) {
+        self.objects.reserve(mesh.triangle_count());
+
+        for triangle in TriangleMesh::triangles(mesh) {
+            self.add(triangle);
+        }
+    }
+
+    /// Adds instance which can be moved later with `set_instance_transform`. Its geometry
+    /// isn't sampled directly as a light.
+    pub fn add_instance(&mut self, instance: Instance) -> InstanceId {
+        self.instances.push(instance);
+
+        InstanceId(self.instances.len() - 1)
+    }
+
+    /// Changes transform of the instance. Scene keeps tracing the previous transform until
+    /// `rebuild_tlas` is called, so many instances can be moved at once.
+    pub fn set_instance_transform(&mut self, id: InstanceId, transform: Mat4) {
+        self.instances[id.0].set_transform(transform);
     }
 
+    /// Number of objects and instances added to the scene.
+    pub fn object_count(&self) -> usize {
+        self.objects.len() + self.bounded.len() + self.unbounded.len() + self.instances.len()
+    }
+
+    /// Builds the acceleration structure and the light list. Calling it again rebuilds them
+    /// only if objects were added in the meantime.
     pub fn construct_bvh(&mut self) {
-        print!("Constructing BVH for {} objects... ", self.objects.len());
+        // Objects are moved out of `objects`, so lights found by previous calls are kept.
+        let lights = self.objects.iter()
+            .filter(|object| object.is_light())
+            .cloned();
+
+        self.lights.extend(lights);
+
+        let (bounded, unbounded): (Vec<_>, Vec<_>) = std::mem::take(&mut self.objects)
+            .into_iter()
+            .partition(|object| object.is_bounded());
 
-        io::stdout().flush().unwrap();
+        self.unbounded.extend(unbounded);
 
-        let start_time = Instant::now();
+        if !bounded.is_empty() {
+            self.bounded.extend(bounded);
+            self.bvh = Some(Bvh::new(self.bounded.clone(), &self.settings));
+        }
+
+        self.rebuild_tlas();
+    }
 
-        self.bvh_root = Some(BvhNode::new(std::mem::take(&mut self.objects)));
+    /// Rebuilds the top level BVH from current instance transforms. Bottom level structures
+    /// of the instanced objects are shared, so this only costs as much as the instance count.
+    pub fn rebuild_tlas(&mut self) {
+        if self.instances.is_empty() {
+            self.tlas = None;
+
+            return;
+        }
+
+        let instances = self.instances.iter()
+            .map(|instance| Arc::new(instance.clone()) as Arc<DynTraceable>)
+            .collect();
+
+        self.tlas = Some(Bvh::new(instances, &self.settings));
+    }
+}
 
-        println!("done in {:.3}s.", start_time.elapsed().as_secs_f64());
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
     }
 }
@@ -0,0 +1,517 @@
+//! Wavefront OBJ and MTL importer.
+//!
+//! Faces are grouped into one `TriangleMesh` per material, polygons with more than three
+//! vertices are fan triangulated. MTL materials are mapped onto the existing material types:
+//!
+//! * non-black emissive color (`Ke`) produces a `DiffuseLight`,
+//! * `illum` 4, 6, 7 or 9, or dissolve below 1 (`d` / `Tr`) produce a `Dielectric` with
+//!   the `Ni` index of refraction,
+//! * `illum` 3 or 5, or a black diffuse color with a non-black specular one, produce a
+//!   `Metal` with `Ks` albedo and fuzziness derived from the `Ns` specular exponent,
+//! * everything else produces a `Lambertian` using `map_Kd` if present and `Kd` otherwise.
+
+use super::loader::{LoadError, Position};
+use crate::Vec3;
+use crate::traceable::TriangleMesh;
+use crate::texture::PictureTexture;
+use crate::material::{SharedMaterial, Lambertian, Metal, Dielectric, DiffuseLight};
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::fs;
+
+type ParseResult<T> = Result<T, (Position, String)>;
+
+/// Removes comment from the end of `line`. Comments start with `#` at the beginning of a
+/// token, so that `#` can be a part of file and material names.
+fn strip_comment(line: &str) -> &str {
+    let mut token_start = true;
+
+    for (index, character) in line.char_indices() {
+        if character == '#' && token_start {
+            return &line[..index];
+        }
+
+        token_start = character.is_whitespace();
+    }
+
+    line
+}
+
+/// Splits the line into whitespace separated tokens together with their positions.
+fn tokens(line: &str, line_number: usize) -> impl Iterator<Item = (Position, &str)> {
+    line.split_whitespace().map(move |token| {
+        let column = token.as_ptr() as usize - line.as_ptr() as usize + 1;
+
+        (Position { line: line_number, column }, token)
+    })
+}
+
+fn parse_number((position, token): (Position, &str)) -> ParseResult<f32> {
+    match token.parse::<f32>() {
+        Ok(value) if value.is_finite() => Ok(value),
+        _ => Err((position, format!("invalid number `{}`", token))),
+    }
+}
+
+fn parse_numbers<'a>(mut tokens: impl Iterator<Item = (Position, &'a str)>, start: Position,
+                     min: usize, max: usize) -> ParseResult<Vec<f32>> {
+    let mut values = Vec::with_capacity(max);
+
+    for token in tokens.by_ref().take(max) {
+        values.push(parse_number(token)?);
+    }
+
+    if values.len() < min {
+        return Err((start, format!("expected at least {} numbers, found {}", min,
+                                   values.len())));
+    }
+
+    if let Some((position, token)) = tokens.next() {
+        return Err((position, format!("unexpected `{}`", token)));
+    }
+
+    Ok(values)
+}
+
+fn rest_of_line<'a>(line: &'a str, keyword: &str, position: Position) -> ParseResult<&'a str> {
+    let rest = line.trim_start()[keyword.len()..].trim();
+
+    if rest.is_empty() {
+        Err((position, format!("`{}` expects an argument", keyword)))
+    } else {
+        Ok(rest)
+    }
+}
+
+#[derive(Clone)]
+struct MtlMaterial {
+    diffuse:     Vec3,
+    specular:    Vec3,
+    emission:    Vec3,
+    exponent:    f32,
+    ior:         f32,
+    dissolve:    f32,
+    illum:       u32,
+    diffuse_map: Option<PathBuf>,
+    position:    Position,
+}
+
+impl MtlMaterial {
+    fn new(position: Position) -> Self {
+        Self {
+            diffuse:     Vec3::fill(0.8),
+            specular:    Vec3::zero(),
+            emission:    Vec3::zero(),
+            exponent:    0.0,
+            ior:         1.5,
+            dissolve:    1.0,
+            illum:       2,
+            diffuse_map: None,
+            position,
+        }
+    }
+
+    /// Creates the material, the path of its texture is appended to `files`.
+    fn build(&self, path: &Path, files: &mut Vec<PathBuf>) -> Result<SharedMaterial, LoadError> {
+        let is_black = |color: Vec3| {
+            let (r, g, b) = color.extract();
+
+            r.max(g).max(b) <= 0.0
+        };
+
+        let transparent = self.dissolve < 1.0 || [4, 6, 7, 9].contains(&self.illum);
+        let metallic    = [3, 5].contains(&self.illum) ||
+            (is_black(self.diffuse) && self.diffuse_map.is_none() && !is_black(self.specular));
+
+        if !is_black(self.emission) {
+            return Ok(DiffuseLight::new(self.emission));
+        }
+
+        if transparent {
+            return Ok(Dielectric::new(self.ior.max(1.0)));
+        }
+
+        if metallic {
+            // Map Phong exponent to roughness in the same way as Blinn-Phong to Beckmann
+            // conversions do.
+            let fuzziness = (2.0 / (self.exponent.max(0.0) + 2.0)).sqrt();
+
+            return Ok(Metal::new(self.specular, fuzziness));
+        }
+
+        match &self.diffuse_map {
+            Some(map) => {
+                let texture = PictureTexture::open(map).map_err(|err| {
+                    LoadError::Syntax(path.to_path_buf(), self.position,
+                                      format!("failed to open texture `{}`: {}",
+                                              map.display(), err))
+                })?;
+
+                files.push(map.clone());
+
+                Ok(Lambertian::new(texture))
+            }
+            None => Ok(Lambertian::new_solid(self.diffuse)),
+        }
+    }
+}
+
+fn parse_mtl(source: &str, directory: &Path) -> ParseResult<Vec<(String, MtlMaterial)>> {
+    let mut materials: Vec<(String, MtlMaterial)> = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let line        = strip_comment(line);
+
+        let mut tokens = tokens(line, line_number);
+
+        let (position, keyword) = match tokens.next() {
+            Some(token) => token,
+            None        => continue,
+        };
+
+        if keyword == "newmtl" {
+            let name = rest_of_line(line, keyword, position)?;
+
+            materials.push((name.to_string(), MtlMaterial::new(position)));
+
+            continue;
+        }
+
+        let material = match materials.last_mut() {
+            Some((_, material)) => material,
+            None => {
+                return Err((position, format!("`{}` used before any `newmtl`", keyword)));
+            }
+        };
+
+        let color = |tokens| -> ParseResult<Vec3> {
+            let values = parse_numbers(tokens, position, 1, 3)?;
+
+            Ok(match values.len() {
+                1 => Vec3::fill(values[0]),
+                3 => Vec3::new(values[0], values[1], values[2]),
+                _ => return Err((position, String::from("expected 1 or 3 color components"))),
+            })
+        };
+
+        let number = |tokens| -> ParseResult<f32> {
+            Ok(parse_numbers(tokens, position, 1, 1)?[0])
+        };
+
+        match keyword {
+            "Kd"     => material.diffuse  = color(tokens)?,
+            "Ks"     => material.specular = color(tokens)?,
+            "Ke"     => material.emission = color(tokens)?,
+            "Ns"     => material.exponent = number(tokens)?,
+            "Ni"     => material.ior      = number(tokens)?,
+            "d"      => material.dissolve = number(tokens)?,
+            "Tr"     => material.dissolve = 1.0 - number(tokens)?,
+            "illum"  => {
+                let (position, token) = tokens.next()
+                    .ok_or_else(|| (position, String::from("`illum` expects a model number")))?;
+
+                material.illum = token.parse()
+                    .map_err(|_| (position, format!("invalid illumination model `{}`", token)))?;
+            }
+            "map_Kd" => {
+                // Texture options precede the file name, which is always the last argument.
+                let rest = rest_of_line(line, keyword, position)?;
+                let file = rest.split_whitespace().last().unwrap();
+
+                material.diffuse_map = Some(directory.join(file));
+            }
+            // Other statements (ambient color, bump maps, ...) have no equivalent in the
+            // renderer and are ignored.
+            _ => (),
+        }
+    }
+
+    Ok(materials)
+}
+
+fn load_mtl(path: &Path, files: &mut Vec<PathBuf>)
+    -> Result<Vec<(String, SharedMaterial)>, LoadError>
+{
+    let source = fs::read_to_string(path)
+        .map_err(|err| LoadError::Io(path.to_path_buf(), err))?;
+
+    files.push(path.to_path_buf());
+
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let materials = parse_mtl(&source, directory)
+        .map_err(|(position, message)| LoadError::Syntax(path.to_path_buf(), position, message))?;
+
+    materials.into_iter()
+        .map(|(name, material)| Ok((name, material.build(path, files)?)))
+        .collect()
+}
+
+#[derive(Default)]
+struct MeshBuilder {
+    positions: Vec<Vec3>,
+    normals:   Vec<Vec3>,
+    uvs:       Vec<(f32, f32)>,
+    indices:   Vec<[u32; 3]>,
+    vertices:  HashMap<(usize, Option<usize>, Option<usize>), u32>,
+}
+
+/// Key of the mesh a face is added to. Faces are split by material and by the set of
+/// attributes they provide as all vertices of a `TriangleMesh` must have the same attributes.
+type MeshKey = (usize, bool, bool);
+
+struct ObjParser<'a> {
+    path:      &'a Path,
+    directory: &'a Path,
+    positions: Vec<Vec3>,
+    normals:   Vec<Vec3>,
+    uvs:       Vec<(f32, f32)>,
+    materials: Vec<SharedMaterial>,
+    names:     HashMap<String, usize>,
+    current:   usize,
+    /// MTL libraries and material selections are skipped when all faces use one material.
+    skip_mtl:  bool,
+    files:     &'a mut Vec<PathBuf>,
+    meshes:    HashMap<MeshKey, MeshBuilder>,
+    order:     Vec<MeshKey>,
+}
+
+impl<'a> ObjParser<'a> {
+    fn resolve_index((position, token): (Position, &str), index: &str, count: usize,
+                     what: &str) -> ParseResult<usize> {
+        let value: i64 = index.parse()
+            .map_err(|_| (position, format!("invalid {} index in `{}`", what, token)))?;
+
+        // Negative indices are relative to the end of the currently defined elements.
+        let resolved = if value < 0 {
+            count as i64 + value
+        } else {
+            value - 1
+        };
+
+        if value == 0 || resolved < 0 || resolved >= count as i64 {
+            return Err((position, format!("{} index {} is out of bounds ({} defined)",
+                                          what, value, count)));
+        }
+
+        Ok(resolved as usize)
+    }
+
+    fn face_vertex(&self, (position, token): (Position, &str))
+        -> ParseResult<(usize, Option<usize>, Option<usize>)>
+    {
+        let mut parts = token.split('/');
+
+        let vertex = Self::resolve_index((position, token), parts.next().unwrap(),
+                                         self.positions.len(), "vertex")?;
+
+        let mut optional = |what, count| -> ParseResult<Option<usize>> {
+            match parts.next() {
+                None | Some("") => Ok(None),
+                Some(index)     => Ok(Some(Self::resolve_index((position, token), index, count,
+                                                               what)?)),
+            }
+        };
+
+        let uv     = optional("texture coordinate", self.uvs.len())?;
+        let normal = optional("normal", self.normals.len())?;
+
+        if parts.next().is_some() {
+            return Err((position, format!("invalid face vertex `{}`", token)));
+        }
+
+        Ok((vertex, uv, normal))
+    }
+
+    fn face<'b>(&mut self, tokens: impl Iterator<Item = (Position, &'b str)>,
+                position: Position) -> ParseResult<()> {
+        let vertices = tokens
+            .map(|token| self.face_vertex(token))
+            .collect::<ParseResult<Vec<_>>>()?;
+
+        if vertices.len() < 3 {
+            return Err((position, format!("face needs at least 3 vertices, found {}",
+                                          vertices.len())));
+        }
+
+        let has_uvs     = vertices.iter().all(|v| v.1.is_some());
+        let has_normals = vertices.iter().all(|v| v.2.is_some());
+
+        let key = (self.current, has_uvs, has_normals);
+
+        if !self.meshes.contains_key(&key) {
+            self.order.push(key);
+        }
+
+        let mesh = self.meshes.entry(key).or_default();
+
+        let mut indices = Vec::with_capacity(vertices.len());
+
+        for (vertex, uv, normal) in vertices {
+            let uv     = uv.filter(|_| has_uvs);
+            let normal = normal.filter(|_| has_normals);
+
+            let index = match mesh.vertices.get(&(vertex, uv, normal)) {
+                Some(&index) => index,
+                None         => {
+                    let index = mesh.positions.len() as u32;
+
+                    mesh.positions.push(self.positions[vertex]);
+
+                    if let Some(uv) = uv {
+                        mesh.uvs.push(self.uvs[uv]);
+                    }
+
+                    if let Some(normal) = normal {
+                        mesh.normals.push(self.normals[normal]);
+                    }
+
+                    mesh.vertices.insert((vertex, uv, normal), index);
+
+                    index
+                }
+            };
+
+            indices.push(index);
+        }
+
+        // Fan triangulation of the polygon.
+        for i in 1..indices.len() - 1 {
+            mesh.indices.push([indices[0], indices[i], indices[i + 1]]);
+        }
+
+        Ok(())
+    }
+
+    fn parse(&mut self, source: &str) -> Result<(), LoadError> {
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let line        = strip_comment(line);
+
+            let mut tokens = tokens(line, line_number);
+
+            let (position, keyword) = match tokens.next() {
+                Some(token) => token,
+                None        => continue,
+            };
+
+            if keyword == "mtllib" {
+                if self.skip_mtl {
+                    continue;
+                }
+
+                let files = rest_of_line(line, keyword, position)
+                    .map_err(|(position, message)| self.error(position, message))?;
+
+                for file in files.split_whitespace() {
+                    for (name, material) in load_mtl(&self.directory.join(file), self.files)? {
+                        self.names.insert(name, self.materials.len());
+                        self.materials.push(material);
+                    }
+                }
+
+                continue;
+            }
+
+            let result = match keyword {
+                "v" => parse_numbers(tokens, position, 3, 4).map(|v| {
+                    self.positions.push(Vec3::new(v[0], v[1], v[2]));
+                }),
+                "vn" => parse_numbers(tokens, position, 3, 3).map(|v| {
+                    self.normals.push(Vec3::new(v[0], v[1], v[2]));
+                }),
+                "vt" => parse_numbers(tokens, position, 1, 3).map(|v| {
+                    self.uvs.push((v[0], v.get(1).copied().unwrap_or(0.0)));
+                }),
+                "f" => self.face(tokens, position),
+                "usemtl" if self.skip_mtl => Ok(()),
+                "usemtl" => rest_of_line(line, keyword, position).and_then(|name| {
+                    self.current = *self.names.get(name).ok_or_else(|| {
+                        (position, format!("unknown material `{}`", name))
+                    })?;
+
+                    Ok(())
+                }),
+                // Groups, objects, smoothing groups and free-form geometry don't affect
+                // the imported triangles.
+                _ => Ok(()),
+            };
+
+            result.map_err(|(position, message)| self.error(position, message))?;
+        }
+
+        Ok(())
+    }
+
+    fn error(&self, position: Position, message: String) -> LoadError {
+        LoadError::Syntax(self.path.to_path_buf(), position, message)
+    }
+}
+
+/// Loads all triangles from the OBJ file at `path`. When `material` is specified it is used
+/// for all faces and MTL libraries aren't read, otherwise materials come from the MTL
+/// libraries referenced by the file.
+pub fn load(path: impl AsRef<Path>, material: Option<&SharedMaterial>)
+    -> Result<Vec<Arc<TriangleMesh>>, LoadError>
+{
+    load_with_files(path, material, &mut Vec::new())
+}
+
+/// Like `load`, but also appends paths of all files it reads, starting with `path`, to
+/// `files`.
+pub fn load_with_files(path: impl AsRef<Path>, material: Option<&SharedMaterial>,
+                       files: &mut Vec<PathBuf>) -> Result<Vec<Arc<TriangleMesh>>, LoadError>
+{
+    let path = path.as_ref();
+
+    let source = fs::read_to_string(path)
+        .map_err(|err| LoadError::Io(path.to_path_buf(), err))?;
+
+    files.push(path.to_path_buf());
+
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+
+    // Faces which don't use any MTL material get a neutral diffuse one, unless all faces
+    // use the given material.
+    let default = match material {
+        Some(material) => material.clone(),
+        None           => Lambertian::new_solid(Vec3::fill(0.8)),
+    };
+
+    let mut parser = ObjParser {
+        path,
+        directory,
+        positions: Vec::new(),
+        normals:   Vec::new(),
+        uvs:       Vec::new(),
+        materials: vec![default],
+        names:     HashMap::new(),
+        current:   0,
+        skip_mtl:  material.is_some(),
+        files,
+        meshes:    HashMap::new(),
+        order:     Vec::new(),
+    };
+
+    parser.parse(&source)?;
+
+    let mut meshes = Vec::with_capacity(parser.order.len());
+
+    for key in &parser.order {
+        let mesh     = parser.meshes.remove(key).unwrap();
+        let (_, has_uvs, has_normals) = *key;
+
+        meshes.push(TriangleMesh::new(
+            mesh.positions,
+            Some(mesh.normals).filter(|_| has_normals),
+            Some(mesh.uvs).filter(|_| has_uvs),
+            mesh.indices,
+            &parser.materials[key.0],
+        ));
+    }
+
+    Ok(meshes)
+}
@@ -0,0 +1,122 @@
+//! Wavelength sampling and conversion of radiance carried at single wavelengths back to RGB,
+//! used by spectral rendering.
+
+use crate::Vec3;
+
+/// Shortest sampled wavelength in nanometres.
+pub const MIN_WAVELENGTH: f32 = 380.0;
+
+/// Longest sampled wavelength in nanometres.
+pub const MAX_WAVELENGTH: f32 = 780.0;
+
+/// Wavelengths splitting the spectrum into the blue, green and red bands used to turn RGB
+/// colors into spectra.
+const BANDS: [f32; 2] = [490.0, 590.0];
+
+/// Maps uniform random number `u` in the [0, 1) range to a wavelength.
+pub fn wavelength(u: f32) -> f32 {
+    MIN_WAVELENGTH + u * (MAX_WAVELENGTH - MIN_WAVELENGTH)
+}
+
+/// Gaussian with different widths below and above its `mean`.
+fn lobe(wavelength: f32, mean: f32, below: f32, above: f32) -> f32 {
+    let t = (wavelength - mean) / if wavelength < mean { below } else { above };
+
+    (-0.5 * t * t).exp()
+}
+
+/// CIE 1931 2° color matching functions at `wavelength` in nanometres. "Simple Analytic
+/// Approximations to the CIE XYZ Color Matching Functions" by Wyman et al.
+pub fn xyz_matching(wavelength: f32) -> Vec3 {
+    let x = 1.056 * lobe(wavelength, 599.8, 37.9, 31.0) +
+            0.362 * lobe(wavelength, 442.0, 16.0, 26.7) -
+            0.065 * lobe(wavelength, 501.1, 20.4, 26.2);
+    let y = 0.821 * lobe(wavelength, 568.8, 46.9, 40.5) +
+            0.286 * lobe(wavelength, 530.9, 16.3, 31.1);
+    let z = 1.217 * lobe(wavelength, 437.0, 11.8, 36.0) +
+            0.681 * lobe(wavelength, 459.0, 26.0, 13.8);
+
+    Vec3::new(x, y, z)
+}
+
+/// Converts CIE XYZ color to linear sRGB.
+pub fn xyz_to_rgb(xyz: Vec3) -> Vec3 {
+    let (x, y, z) = xyz.extract();
+
+    Vec3::new( 3.2406 * x - 1.5372 * y - 0.4986 * z,
+              -0.9689 * x + 1.8758 * y + 0.0415 * z,
+               0.0557 * x - 0.2040 * y + 1.0570 * z)
+}
+
+/// Linear sRGB response to radiance carried at a single wavelength, together with its
+/// inverse turning RGB colors into spectra.
+#[derive(Copy, Clone)]
+pub struct SpectralResponse {
+    scale:  Vec3,
+    /// Rows convert RGB color to heights of the blue, green and red bands of its spectrum.
+    uplift: [Vec3; 3],
+}
+
+impl SpectralResponse {
+    pub fn new() -> Self {
+        const STEPS: usize = 1000;
+
+        let range = MAX_WAVELENGTH - MIN_WAVELENGTH;
+        let step  = range / STEPS as f32;
+
+        let wavelengths = (0..STEPS).map(|i| MIN_WAVELENGTH + (i as f32 + 0.5) * step);
+
+        let integral = wavelengths.clone()
+            .map(|wavelength| xyz_to_rgb(xyz_matching(wavelength)))
+            .fold(Vec3::zero(), |sum, rgb| sum + rgb) * step;
+
+        // Scale each channel so that spectrum with equal energy at all wavelengths stays
+        // white, like in RGB rendering.
+        let mut response = Self {
+            scale:  Vec3::fill(range) / integral,
+            uplift: [Vec3::zero(); 3],
+        };
+
+        // Color of every band at unit height. Together they form a matrix whose inverse
+        // turns colors back into band heights, so colors survive the round trip.
+        let mut bands = [Vec3::zero(); 3];
+
+        for wavelength in wavelengths {
+            bands[band(wavelength)] += response.weight(wavelength) * (step / range);
+        }
+
+        let [b, g, r] = bands;
+        let det       = Vec3::dot(b, Vec3::cross(g, r));
+
+        response.uplift = [Vec3::cross(g, r) / det, Vec3::cross(r, b) / det,
+                           Vec3::cross(b, g) / det];
+
+        response
+    }
+
+    /// Weight of radiance carried at `wavelength` in the pixel color. Wavelengths are sampled
+    /// uniformly, so the weight averaged over them is one in every channel. It can be
+    /// negative for spectral colors, which lie outside of the sRGB gamut.
+    pub fn weight(&self, wavelength: f32) -> Vec3 {
+        xyz_to_rgb(xyz_matching(wavelength)) * self.scale
+    }
+
+    /// Value at `wavelength` of a smooth enough spectrum with linear sRGB `color`. Spectra
+    /// are constant over the blue, green and red bands. Gray colors give flat spectra, and
+    /// `weight` averaged over a spectrum gives back its color, unless the color is so
+    /// saturated that a band would be negative.
+    pub fn uplift(&self, color: Vec3, wavelength: f32) -> f32 {
+        Vec3::dot(self.uplift[band(wavelength)], color).max(0.0)
+    }
+}
+
+/// Index of the blue, green or red band containing `wavelength`.
+fn band(wavelength: f32) -> usize {
+    BANDS.iter().filter(|&&edge| wavelength >= edge).count()
+}
+
+impl Default for SpectralResponse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,3 +1,5 @@
+//! Textures providing spatially varying colors.
+
 mod solid;
 mod picture;
 
@@ -8,9 +10,11 @@ use crate::Vec3;
 pub use solid::SolidTexture;
 pub use picture::PictureTexture;
 
+/// Texture which can be shared between materials and render threads.
 pub type SharedTexture = Arc<dyn Texture + Send + Sync>;
 
 pub trait Texture {
+    /// Returns color at texture coordinates `u`, `v` of point `p`.
     fn color(&self, u: f32, v: f32, p: Vec3) -> Vec3;
 }
 
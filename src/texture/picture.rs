@@ -1,15 +1,23 @@
 use super::{Texture, SharedTexture};
 use crate::Vec3;
 
+use std::path::Path;
+
+use image::ImageError;
+
 pub struct PictureTexture {
     image: image::RgbImage,
 }
 
 impl PictureTexture {
     pub fn new(path: &str) -> SharedTexture {
-        super::make_shared(Self {
-            image: image::open(path).unwrap().into_rgb(),
-        })
+        Self::open(path).expect("Failed to open texture image.")
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<SharedTexture, ImageError> {
+        Ok(super::make_shared(Self {
+            image: image::open(path)?.into_rgb(),
+        }))
     }
 }
 
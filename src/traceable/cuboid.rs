@@ -0,0 +1,144 @@
+use super::{HitRecord, Traceable};
+use crate::{Vec3, Ray};
+use crate::math::AABB;
+use crate::material::SharedMaterial;
+
+/// Converts position on a face, from -1 to 1 along its edges, to texture coordinates.
+fn face_uv(record: &HitRecord) -> (f32, f32) {
+    let (u, v) = record.local;
+
+    (((u + 1.0) * 0.5).clamp(0.0, 1.0), ((v + 1.0) * 0.5).clamp(0.0, 1.0))
+}
+
+/// Rectangular box, optionally rotated. Texture coordinates span every face separately.
+pub struct Cuboid {
+    center:    Vec3,
+    half_size: [f32; 3],
+    axes:      [Vec3; 3],
+    material:  SharedMaterial,
+}
+
+impl Cuboid {
+    /// Creates axis aligned box spanning from `min` to `max`.
+    pub fn new(min: Vec3, max: Vec3, material: &SharedMaterial) -> Self {
+        Self::oriented((min + max) * 0.5, max - min, Vec3::new(1.0, 0.0, 0.0),
+                       Vec3::new(0.0, 1.0, 0.0), material)
+    }
+
+    /// Creates box of given `size` centered at `center`, with its local X axis pointing
+    /// along `x_axis` and Y axis as close to `y_axis` as possible.
+    pub fn oriented(center: Vec3, size: Vec3, x_axis: Vec3, y_axis: Vec3,
+                    material: &SharedMaterial) -> Self {
+        let x = x_axis.normalized();
+        let z = Vec3::cross(x, y_axis).normalized();
+        let y = Vec3::cross(z, x);
+
+        let (sx, sy, sz) = size.extract();
+
+        assert!(sx > 0.0 && sy > 0.0 && sz > 0.0, "Box size must be positive.");
+
+        Self {
+            center,
+            half_size: [sx * 0.5, sy * 0.5, sz * 0.5],
+            axes:      [x, y, z],
+            material:  material.clone(),
+        }
+    }
+
+    /// Converts `vector` to the box coordinate system.
+    fn to_local(&self, vector: Vec3) -> [f32; 3] {
+        let [x, y, z] = self.axes;
+
+        [Vec3::dot(vector, x), Vec3::dot(vector, y), Vec3::dot(vector, z)]
+    }
+
+    /// Intersects ray with `origin` and `direction` in the box coordinate system. Returns ray
+    /// parameter of the hit between `min_t` and `max_t`, the axis of the hit face and whether
+    /// the ray enters the box there.
+    #[inline(always)]
+    fn intersect(&self, origin: &[f32; 3], direction: &[f32; 3], min_t: f32, max_t: f32)
+        -> Option<(f32, usize, bool)>
+    {
+        // Slab test which also remembers which axes the entry and exit points lie on.
+        let mut near = (f32::NEG_INFINITY, 0);
+        let mut far  = (f32::INFINITY, 0);
+
+        for axis in 0..3 {
+            let inverse = 1.0 / direction[axis];
+
+            let t0 = (-self.half_size[axis] - origin[axis]) * inverse;
+            let t1 = ( self.half_size[axis] - origin[axis]) * inverse;
+
+            let (t0, t1) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+
+            if t0 > near.0 {
+                near = (t0, axis);
+            }
+
+            if t1 < far.0 {
+                far = (t1, axis);
+            }
+
+            // NaN slabs (rays parallel to and lying on a face) never update `near` or `far`.
+            if near.0 > far.0 {
+                return None;
+            }
+        }
+
+        // If the entry point is behind the ray origin we are inside the box and hit the exit.
+        let (t, axis, entering) = if near.0 > min_t {
+            (near.0, near.1, true)
+        } else {
+            (far.0, far.1, false)
+        };
+
+        if t >= max_t || t <= min_t {
+            return None;
+        }
+
+        Some((t, axis, entering))
+    }
+}
+
+impl Traceable for Cuboid {
+    fn trace(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<HitRecord<'_>> {
+        let origin    = self.to_local(ray.origin - self.center);
+        let direction = self.to_local(ray.direction);
+
+        let (t, axis, entering) = self.intersect(&origin, &direction, min_t, max_t)?;
+
+        // Normal points out of the box, away from the ray when entering and along it when
+        // exiting.
+        let sign   = if (direction[axis] < 0.0) == entering { 1.0 } else { -1.0 };
+        let normal = self.axes[axis] * sign;
+
+        let face  = |axis: usize| (origin[axis] + direction[axis] * t) / self.half_size[axis];
+        let local = (face((axis + 1) % 3), face((axis + 2) % 3));
+
+        let mut record = HitRecord::with_local(t, ray.point(t), normal, &*self.material, local,
+                                               face_uv);
+
+        record.object = Some(self);
+
+        Some(record)
+    }
+
+    fn occluded(&self, ray: &Ray, min_t: f32, max_t: f32) -> bool {
+        let origin    = self.to_local(ray.origin - self.center);
+        let direction = self.to_local(ray.direction);
+
+        self.intersect(&origin, &direction, min_t, max_t).is_some()
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let mut extent = Vec3::zero();
+
+        for (axis, half_size) in self.axes.iter().zip(&self.half_size) {
+            let (x, y, z) = axis.extract();
+
+            extent += Vec3::new(x.abs(), y.abs(), z.abs()) * *half_size;
+        }
+
+        AABB::new(self.center - extent, self.center + extent)
+    }
+}
@@ -0,0 +1,126 @@
+use super::{HitRecord, Traceable, LightSample};
+use crate::{Vec3, Ray};
+use crate::math::{self, AABB};
+use crate::material::SharedMaterial;
+use crate::rng::Rng;
+
+use std::f32::consts::PI;
+
+/// Converts position on the disc relative to its center and radius to texture coordinates.
+fn disc_uv(record: &HitRecord) -> (f32, f32) {
+    let (x, y) = record.local;
+
+    ((y.atan2(x) + PI) / (2.0 * PI), (x * x + y * y).sqrt())
+}
+
+/// Flat circle facing `normal`. U texture coordinate goes around the disc and V from its
+/// center to the edge.
+pub struct Disc {
+    center:    Vec3,
+    normal:    Vec3,
+    radius:    f32,
+    tangent:   Vec3,
+    bitangent: Vec3,
+    material:  SharedMaterial,
+}
+
+impl Disc {
+    pub fn new(center: Vec3, normal: Vec3, radius: f32, material: &SharedMaterial) -> Self {
+        assert!(radius > 0.0, "Disc radius must be positive.");
+
+        let normal               = normal.normalized();
+        let (tangent, bitangent) = math::orthonormal_basis(normal);
+
+        Self {
+            center,
+            normal,
+            radius,
+            tangent,
+            bitangent,
+            material: material.clone(),
+        }
+    }
+
+    fn record(&self, t: f32, point: Vec3) -> HitRecord<'_> {
+        let offset = point - self.center;
+
+        let local = (Vec3::dot(offset, self.tangent) / self.radius,
+                     Vec3::dot(offset, self.bitangent) / self.radius);
+
+        let mut record = HitRecord::with_local(t, point, self.normal, &*self.material, local,
+                                               disc_uv);
+
+        record.object = Some(self);
+        record
+    }
+
+    fn area(&self) -> f32 {
+        PI * self.radius * self.radius
+    }
+
+    /// Returns ray parameter and hit point of the intersection with `ray` if it lies between
+    /// `min_t` and `max_t`.
+    #[inline(always)]
+    fn intersect(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<(f32, Vec3)> {
+        let denominator = Vec3::dot(self.normal, ray.direction);
+
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = Vec3::dot(self.normal, self.center - ray.origin) / denominator;
+
+        if t >= max_t || t <= min_t {
+            return None;
+        }
+
+        let point = ray.point(t);
+
+        if (point - self.center).length_sqr() > self.radius * self.radius {
+            return None;
+        }
+
+        Some((t, point))
+    }
+}
+
+impl Traceable for Disc {
+    fn trace(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<HitRecord<'_>> {
+        let (t, point) = self.intersect(ray, min_t, max_t)?;
+
+        Some(self.record(t, point))
+    }
+
+    fn occluded(&self, ray: &Ray, min_t: f32, max_t: f32) -> bool {
+        self.intersect(ray, min_t, max_t).is_some()
+    }
+
+    fn bounding_box(&self) -> AABB {
+        // Extent of the disc along every axis depends on how much it's tilted away from it.
+        let (x, y, z) = self.normal.extract();
+
+        let extent = |n: f32| self.radius * (1.0 - n * n).max(0.0).sqrt() + super::FLAT_PADDING;
+        let extent = Vec3::new(extent(x), extent(y), extent(z));
+
+        AABB::new(self.center - extent, self.center + extent)
+    }
+
+    fn is_light(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn sample_light(&self, origin: Vec3, rng: &mut Rng) -> Option<LightSample> {
+        let radius     = self.radius * rng.rand::<f32>().sqrt();
+        let (sin, cos) = (2.0 * PI * rng.rand::<f32>()).sin_cos();
+
+        let point = self.center + self.tangent * (radius * cos) + self.bitangent * (radius * sin);
+
+        super::sample_flat_light(origin, point, self.normal, self.area(), |ray, distance| {
+            self.material.emitted(ray, &self.record(distance, point))
+        })
+    }
+
+    fn light_pdf(&self, origin: Vec3, record: &HitRecord) -> f32 {
+        super::area_to_solid_angle_pdf(origin, record.point, self.normal, self.area())
+    }
+}
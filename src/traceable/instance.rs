@@ -0,0 +1,106 @@
+use super::{HitRecord, Traceable, DynTraceable};
+use crate::{Vec3, Ray};
+use crate::math::{AABB, Mat4};
+
+use std::sync::Arc;
+
+/// Shared object placed in the scene with an affine transform. Many instances can refer to
+/// the same object without duplicating its data.
+#[derive(Clone)]
+pub struct Instance {
+    object:    Arc<DynTraceable>,
+    transform: Mat4,
+    inverse:   Mat4,
+    bbox:      AABB,
+}
+
+impl Instance {
+    /// Places `object` in the scene by transforming it from its own space with `transform`.
+    pub fn new(object: Arc<DynTraceable>, transform: Mat4) -> Self {
+        assert!(object.is_bounded(), "Only bounded objects can be instanced.");
+
+        let bbox = object.bounding_box();
+
+        let mut instance = Self {
+            object,
+            transform: Mat4::identity(),
+            inverse:   Mat4::identity(),
+            bbox,
+        };
+
+        instance.set_transform(transform);
+        instance
+    }
+
+    pub fn transform(&self) -> Mat4 {
+        self.transform
+    }
+
+    /// Moves the instance. The instanced object itself is left untouched.
+    pub fn set_transform(&mut self, transform: Mat4) {
+        let inverse = transform.inverse().expect("Instance transform must be invertible.");
+
+        // Transformed box of the object is enclosed by the bounds of its transformed corners.
+        let local   = self.object.bounding_box();
+        let corners = (0..8).map(|i| {
+            let pick = |bit: usize, min: f32, max: f32| if i & bit == 0 { min } else { max };
+
+            let (min, max) = (local.min.extract(), local.max.extract());
+
+            transform.transform_point(Vec3::new(pick(1, min.0, max.0), pick(2, min.1, max.1),
+                                                pick(4, min.2, max.2)))
+        });
+
+        self.bbox = corners.fold(AABB::empty(), |bbox, corner| {
+            AABB::enclosing_box(&bbox, &AABB::new(corner, corner))
+        });
+
+        self.transform = transform;
+        self.inverse   = inverse;
+    }
+}
+
+impl Instance {
+    /// Transforms `ray` into object space. Returns the local ray together with the factor
+    /// which converts world space ray parameters into local ones.
+    fn local_ray(&self, ray: &Ray) -> (Ray, f32) {
+        // Objects expect normalized rays, so distances in object space are scaled by the
+        // length of the transformed direction.
+        let direction = self.inverse.transform_vector(ray.direction);
+        let scale     = direction.length();
+
+        let local = Ray::new_normalized(self.inverse.transform_point(ray.origin),
+                                        direction / scale);
+
+        (local, scale)
+    }
+}
+
+impl Traceable for Instance {
+    fn trace(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<HitRecord<'_>> {
+        let (local, scale) = self.local_ray(ray);
+
+        let record = self.object.trace(&local, min_t * scale, max_t * scale)?;
+        let t      = record.t / scale;
+
+        // UV functions may depend on object space hit point and normal so they are evaluated
+        // here. Nested objects aren't in the scene light list, so `object` stays empty.
+        Some(HitRecord::with_uv(
+            t,
+            self.transform.transform_point(record.point),
+            self.inverse.transform_normal(record.normal).normalized(),
+            record.material,
+            record.uv(),
+        ))
+    }
+
+    fn occluded(&self, ray: &Ray, min_t: f32, max_t: f32) -> bool {
+        let (local, scale) = self.local_ray(ray);
+
+        self.object.occluded(&local, min_t * scale, max_t * scale)
+    }
+
+    fn bounding_box(&self) -> AABB {
+        self.bbox
+    }
+}
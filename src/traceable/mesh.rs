@@ -0,0 +1,76 @@
+use super::Triangle;
+use crate::Vec3;
+use crate::material::SharedMaterial;
+
+use std::sync::Arc;
+
+/// Indexed triangle mesh. Vertex attributes are shared between all triangles of the mesh and
+/// every `indices` entry references one vertex in each of the attribute buffers.
+pub struct TriangleMesh {
+    positions: Vec<Vec3>,
+    normals:   Option<Vec<Vec3>>,
+    uvs:       Option<Vec<(f32, f32)>>,
+    indices:   Vec<[u32; 3]>,
+    material:  SharedMaterial,
+}
+
+impl TriangleMesh {
+    pub fn new(positions: Vec<Vec3>, normals: Option<Vec<Vec3>>, uvs: Option<Vec<(f32, f32)>>,
+               indices: Vec<[u32; 3]>, material: &SharedMaterial) -> Arc<Self> {
+        let vertex_count = positions.len();
+
+        if let Some(normals) = &normals {
+            assert!(normals.len() == vertex_count, "Mesh normal count doesn't match vertex count.");
+        }
+
+        if let Some(uvs) = &uvs {
+            assert!(uvs.len() == vertex_count, "Mesh UV count doesn't match vertex count.");
+        }
+
+        assert!(vertex_count <= u32::MAX as usize, "Mesh has too many vertices.");
+        assert!(indices.len() <= u32::MAX as usize, "Mesh has too many triangles.");
+        assert!(indices.iter().flatten().all(|&index| (index as usize) < vertex_count),
+                "Mesh index is out of bounds.");
+
+        Arc::new(Self {
+            positions,
+            normals: normals.map(|normals| normals.into_iter().map(|n| n.normalized()).collect()),
+            uvs,
+            indices,
+            material: material.clone(),
+        })
+    }
+
+    pub fn triangles(mesh: &Arc<Self>) -> impl Iterator<Item = Triangle> + '_ {
+        (0..mesh.indices.len() as u32).map(move |index| Triangle::new(mesh, index))
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    #[inline(always)]
+    pub(super) fn vertices(&self, triangle: u32) -> [u32; 3] {
+        self.indices[triangle as usize]
+    }
+
+    #[inline(always)]
+    pub(super) fn position(&self, vertex: u32) -> Vec3 {
+        self.positions[vertex as usize]
+    }
+
+    #[inline(always)]
+    pub(super) fn normal(&self, vertex: u32) -> Option<Vec3> {
+        self.normals.as_ref().map(|normals| normals[vertex as usize])
+    }
+
+    #[inline(always)]
+    pub(super) fn uv(&self, vertex: u32) -> Option<(f32, f32)> {
+        self.uvs.as_ref().map(|uvs| uvs[vertex as usize])
+    }
+
+    #[inline(always)]
+    pub(super) fn material(&self) -> &SharedMaterial {
+        &self.material
+    }
+}
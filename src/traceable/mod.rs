@@ -1,39 +1,167 @@
+//! Geometric objects which rays can intersect.
+
 mod sphere;
+mod triangle;
+mod mesh;
+mod plane;
+mod quad;
+mod disc;
+mod cuboid;
+mod instance;
 
 use crate::{Vec3, Ray};
 use crate::math::AABB;
 use crate::material::Material;
+use crate::rng::Rng;
 
 pub use sphere::Sphere;
+pub use triangle::Triangle;
+pub use mesh::TriangleMesh;
+pub use plane::Plane;
+pub use quad::Quad;
+pub use disc::Disc;
+pub use cuboid::Cuboid;
+pub use instance::Instance;
+
+/// Flat objects would have flat bounding boxes which rays can never intersect, so they are
+/// padded by this amount.
+const FLAT_PADDING: f32 = 1e-4;
 
+/// Traceable object which can be shared between render threads.
 pub type DynTraceable = dyn Traceable + Send + Sync;
 
+/// Intersection of a ray with an object.
 pub struct HitRecord<'a> {
+    /// Ray parameter of the intersection.
     pub t:        f32,
     pub point:    Vec3,
+    /// Unit surface normal, facing outwards of the object.
     pub normal:   Vec3,
     pub material: &'a dyn Material,
+    /// Object which was hit, used to evaluate light sampling PDFs. Objects which aren't
+    /// part of the scene light list (like ones nested in other traceables) leave it empty.
+    pub object:   Option<&'a DynTraceable>,
+    /// Surface coordinates of the hit point which `get_uv` converts to texture coordinates.
+    local:        (f32, f32),
     get_uv:       fn(&HitRecord) -> (f32, f32),
 }
 
 impl<'a> HitRecord<'a> {
+    /// Creates a record which computes texture coordinates lazily using `get_uv`.
     pub fn new(t: f32, point: Vec3, normal: Vec3, material: &'a dyn Material,
                get_uv: fn(&HitRecord) -> (f32, f32)) -> Self {
+        Self::with_local(t, point, normal, material, (0.0, 0.0), get_uv)
+    }
+
+    /// Creates a record with already known texture coordinates. Useful for primitives which
+    /// cannot recover UVs from the hit point and normal alone.
+    pub fn with_uv(t: f32, point: Vec3, normal: Vec3, material: &'a dyn Material,
+                   uv: (f32, f32)) -> Self {
+        Self::with_local(t, point, normal, material, uv, |record| record.local)
+    }
+
+    /// Creates a record which lazily converts `local` surface coordinates to texture
+    /// coordinates using `get_uv`.
+    fn with_local(t: f32, point: Vec3, normal: Vec3, material: &'a dyn Material,
+                  local: (f32, f32), get_uv: fn(&HitRecord) -> (f32, f32)) -> Self {
         Self {
             t,
             point,
             normal,
             material,
             get_uv,
+            object: None,
+            local,
         }
     }
 
+    /// Texture coordinates of the hit point.
     pub fn uv(&self) -> (f32, f32) {
         (self.get_uv)(self)
     }
 }
 
+/// Point on a light source sampled from some shading point.
+pub struct LightSample {
+    pub direction: Vec3,
+    pub distance:  f32,
+    pub pdf:       f32,
+    pub radiance:  Vec3,
+}
+
+/// Object which can be placed in the scene.
 pub trait Traceable {
-    fn trace(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<HitRecord>; 
+    /// Finds the closest intersection with `ray` which lies between `min_t` and `max_t`.
+    fn trace(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<HitRecord<'_>>;
+
+    /// Returns true if `ray` intersects the object anywhere between `min_t` and `max_t`.
+    /// Objects should override this when they can answer without building a `HitRecord`.
+    fn occluded(&self, ray: &Ray, min_t: f32, max_t: f32) -> bool {
+        self.trace(ray, min_t, max_t).is_some()
+    }
+
+    /// Returns box containing the whole object.
     fn bounding_box(&self) -> AABB;
+
+    /// Returns false for infinite objects, which have no meaningful bounding box. Scene
+    /// traces them separately from its acceleration structure.
+    fn is_bounded(&self) -> bool {
+        true
+    }
+
+    /// Returns true if the object emits light and supports `sample_light`.
+    fn is_light(&self) -> bool {
+        false
+    }
+
+    /// Samples direction from `origin` towards the object. PDF of the sample is with
+    /// respect to the solid angle.
+    fn sample_light(&self, _origin: Vec3, _rng: &mut Rng) -> Option<LightSample> {
+        None
+    }
+
+    /// Solid angle PDF of `sample_light` picking the point described by `record` when
+    /// sampling from `origin`.
+    fn light_pdf(&self, _origin: Vec3, _record: &HitRecord) -> f32 {
+        0.0
+    }
+}
+
+/// Converts PDF of uniformly sampling a flat light with `area` to solid angle PDF of picking
+/// `point` from `origin`. `normal` doesn't need to be normalized.
+fn area_to_solid_angle_pdf(origin: Vec3, point: Vec3, normal: Vec3, area: f32) -> f32 {
+    let to_point     = point - origin;
+    let distance_sqr = to_point.length_sqr();
+    let cosine       = Vec3::dot(normal, to_point).abs() /
+        (normal.length() * distance_sqr.sqrt());
+
+    if cosine <= 1e-6 || area <= 0.0 {
+        0.0
+    } else {
+        distance_sqr / (cosine * area)
+    }
+}
+
+/// Builds light sample for `point` uniformly sampled on a flat light. `radiance` gets the
+/// ray going from `origin` to the point and the distance to it.
+fn sample_flat_light(origin: Vec3, point: Vec3, normal: Vec3, area: f32,
+                     radiance: impl FnOnce(&Ray, f32) -> Vec3) -> Option<LightSample> {
+    let pdf = area_to_solid_angle_pdf(origin, point, normal, area);
+
+    if pdf <= 0.0 {
+        return None;
+    }
+
+    let to_point  = point - origin;
+    let distance  = to_point.length();
+    let direction = to_point / distance;
+
+    let ray = Ray::new_normalized(origin, direction);
+
+    Some(LightSample {
+        direction,
+        distance,
+        pdf,
+        radiance: radiance(&ray, distance),
+    })
 }
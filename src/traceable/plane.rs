@@ -0,0 +1,77 @@
+use super::{HitRecord, Traceable};
+use crate::{Vec3, Ray};
+use crate::math::{self, AABB};
+use crate::material::SharedMaterial;
+
+/// Tiles textures once per unit of distance along the plane.
+fn plane_uv(record: &HitRecord) -> (f32, f32) {
+    let (tangent, bitangent) = math::orthonormal_basis(record.normal);
+
+    let u = Vec3::dot(record.point, tangent).rem_euclid(1.0);
+    let v = Vec3::dot(record.point, bitangent).rem_euclid(1.0);
+
+    (u, v)
+}
+
+/// Infinite plane going through `point`. It has no finite bounding box, so the scene traces
+/// it separately from the BVH.
+pub struct Plane {
+    point:    Vec3,
+    normal:   Vec3,
+    material: SharedMaterial,
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, material: &SharedMaterial) -> Self {
+        Self {
+            point,
+            normal:   normal.normalized(),
+            material: material.clone(),
+        }
+    }
+}
+
+impl Plane {
+    /// Returns ray parameter of the intersection with `ray` if it lies between `min_t` and
+    /// `max_t`.
+    #[inline(always)]
+    fn intersect(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<f32> {
+        let denominator = Vec3::dot(self.normal, ray.direction);
+
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = Vec3::dot(self.normal, self.point - ray.origin) / denominator;
+
+        if t >= max_t || t <= min_t {
+            return None;
+        }
+
+        Some(t)
+    }
+}
+
+impl Traceable for Plane {
+    fn trace(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<HitRecord<'_>> {
+        let t = self.intersect(ray, min_t, max_t)?;
+
+        let mut record = HitRecord::new(t, ray.point(t), self.normal, &*self.material, plane_uv);
+
+        record.object = Some(self);
+
+        Some(record)
+    }
+
+    fn occluded(&self, ray: &Ray, min_t: f32, max_t: f32) -> bool {
+        self.intersect(ray, min_t, max_t).is_some()
+    }
+
+    fn bounding_box(&self) -> AABB {
+        AABB::new(Vec3::fill(f32::NEG_INFINITY), Vec3::fill(f32::INFINITY))
+    }
+
+    fn is_bounded(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,110 @@
+use super::{HitRecord, Traceable, LightSample};
+use crate::{Vec3, Ray};
+use crate::math::AABB;
+use crate::material::SharedMaterial;
+use crate::rng::Rng;
+
+/// Parallelogram spanned by edges `u` and `v` going from `corner`.
+pub struct Quad {
+    corner:   Vec3,
+    u:        Vec3,
+    v:        Vec3,
+    normal:   Vec3,
+    /// Vector converting plane positions to coordinates along `u` and `v`.
+    w:        Vec3,
+    area:     f32,
+    material: SharedMaterial,
+}
+
+impl Quad {
+    pub fn new(corner: Vec3, u: Vec3, v: Vec3, material: &SharedMaterial) -> Self {
+        let n    = Vec3::cross(u, v);
+        let area = n.length();
+
+        assert!(area > 0.0, "Quad edges cannot be parallel.");
+
+        Self {
+            corner,
+            u,
+            v,
+            normal:   n / area,
+            w:        n / Vec3::dot(n, n),
+            area,
+            material: material.clone(),
+        }
+    }
+
+    fn record(&self, t: f32, point: Vec3, uv: (f32, f32)) -> HitRecord<'_> {
+        let mut record = HitRecord::with_uv(t, point, self.normal, &*self.material, uv);
+
+        record.object = Some(self);
+        record
+    }
+
+    /// Returns ray parameter, hit point and its coordinates along the edges if `ray` hits the
+    /// quad between `min_t` and `max_t`. The coordinates are also the texture coordinates.
+    #[inline(always)]
+    fn intersect(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<(f32, Vec3, (f32, f32))> {
+        let denominator = Vec3::dot(self.normal, ray.direction);
+
+        if denominator.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = Vec3::dot(self.normal, self.corner - ray.origin) / denominator;
+
+        if t >= max_t || t <= min_t {
+            return None;
+        }
+
+        let point  = ray.point(t);
+        let planar = point - self.corner;
+
+        let alpha = Vec3::dot(self.w, Vec3::cross(planar, self.v));
+        let beta  = Vec3::dot(self.w, Vec3::cross(self.u, planar));
+
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        Some((t, point, (alpha, beta)))
+    }
+}
+
+impl Traceable for Quad {
+    fn trace(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<HitRecord<'_>> {
+        let (t, point, uv) = self.intersect(ray, min_t, max_t)?;
+
+        Some(self.record(t, point, uv))
+    }
+
+    fn occluded(&self, ray: &Ray, min_t: f32, max_t: f32) -> bool {
+        self.intersect(ray, min_t, max_t).is_some()
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let corners = [self.corner + self.u, self.corner + self.v, self.corner + self.u + self.v];
+
+        let min = corners.iter().fold(self.corner, |min, &corner| Vec3::min(min, corner));
+        let max = corners.iter().fold(self.corner, |max, &corner| Vec3::max(max, corner));
+
+        AABB::new(min - Vec3::fill(super::FLAT_PADDING), max + Vec3::fill(super::FLAT_PADDING))
+    }
+
+    fn is_light(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn sample_light(&self, origin: Vec3, rng: &mut Rng) -> Option<LightSample> {
+        let uv    = (rng.rand::<f32>(), rng.rand::<f32>());
+        let point = self.corner + self.u * uv.0 + self.v * uv.1;
+
+        super::sample_flat_light(origin, point, self.normal, self.area, |ray, distance| {
+            self.material.emitted(ray, &self.record(distance, point, uv))
+        })
+    }
+
+    fn light_pdf(&self, origin: Vec3, record: &HitRecord) -> f32 {
+        super::area_to_solid_angle_pdf(origin, record.point, self.normal, self.area)
+    }
+}
@@ -1,7 +1,8 @@
-use super::{HitRecord, Traceable};
+use super::{HitRecord, Traceable, LightSample};
 use crate::{Vec3, Ray};
-use crate::math::AABB;
+use crate::math::{self, AABB};
 use crate::material::SharedMaterial;
+use crate::rng::Rng;
 
 fn sphere_uv(record: &HitRecord) -> (f32, f32) {
     let (x, y, z) = record.normal.extract();
@@ -34,16 +35,20 @@ impl Sphere {
 }
 
 impl Sphere {
-    fn record(&self, t: f32, ray: &Ray) -> HitRecord {
+    fn record(&self, t: f32, ray: &Ray) -> HitRecord<'_> {
         let point     = ray.point(t);
         let direction = (point - self.center).normalized();
 
-        HitRecord::new(t, point, direction, &*self.material, sphere_uv)
+        let mut record = HitRecord::new(t, point, direction, &*self.material, sphere_uv);
+
+        record.object = Some(self);
+        record
     }
-}
 
-impl Traceable for Sphere {
-    fn trace(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<HitRecord> {
+    /// Returns the nearest ray parameter at which `ray` hits the sphere between `min_t` and
+    /// `max_t`.
+    #[inline(always)]
+    fn intersect(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<f32> {
         let oc = ray.origin - self.center;
 
         let a = Vec3::dot(ray.direction, ray.direction);
@@ -55,23 +60,90 @@ impl Traceable for Sphere {
             let sol = (-b - d.sqrt()) / a;
 
             if sol < max_t && sol > min_t {
-                return Some(self.record(sol, ray));
+                return Some(sol);
             }
 
             let sol = (-b + d.sqrt()) / a;
 
             if sol < max_t && sol > min_t {
-                return Some(self.record(sol, ray));
+                return Some(sol);
             }
         }
 
         None
     }
 
+    /// Returns `1 - cos(theta_max)` of the cone containing the sphere when viewed from
+    /// `origin` or `None` if `origin` is inside the sphere.
+    fn cone(&self, origin: Vec3) -> Option<f32> {
+        let distance_sqr = (self.center - origin).length_sqr();
+        let radius_sqr   = self.radius * self.radius;
+
+        if distance_sqr <= radius_sqr {
+            return None;
+        }
+
+        let sin_sqr_max = radius_sqr / distance_sqr;
+        let cos_max     = (1.0 - sin_sqr_max).max(0.0).sqrt();
+
+        // Computing `1 - cos_max` directly loses all precision for small, distant spheres.
+        Some(sin_sqr_max / (1.0 + cos_max))
+    }
+}
+
+impl Traceable for Sphere {
+    fn trace(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<HitRecord<'_>> {
+        self.intersect(ray, min_t, max_t).map(|t| self.record(t, ray))
+    }
+
+    fn occluded(&self, ray: &Ray, min_t: f32, max_t: f32) -> bool {
+        self.intersect(ray, min_t, max_t).is_some()
+    }
+
     fn bounding_box(&self) -> AABB {
         AABB::new(
             self.center - Vec3::fill(self.radius),
             self.center + Vec3::fill(self.radius),
         )
     }
+
+    fn is_light(&self) -> bool {
+        self.material.is_emissive()
+    }
+
+    fn sample_light(&self, origin: Vec3, rng: &mut Rng) -> Option<LightSample> {
+        // Uniformly sample the cone of directions subtended by the sphere.
+        let one_minus_cos_max = self.cone(origin)?;
+
+        let to_center = self.center - origin;
+        let distance  = to_center.length();
+        let w         = to_center / distance;
+        let (u, v)    = math::orthonormal_basis(w);
+
+        let cos = 1.0 - rng.rand::<f32>() * one_minus_cos_max;
+        let sin = (1.0 - cos * cos).max(0.0).sqrt();
+        let phi = 2.0 * std::f32::consts::PI * rng.rand::<f32>();
+
+        let direction = u * (sin * phi.cos()) + v * (sin * phi.sin()) + w * cos;
+
+        let discriminant = self.radius * self.radius - distance * distance * sin * sin;
+        let t            = distance * cos - discriminant.max(0.0).sqrt();
+
+        let ray    = Ray::new_normalized(origin, direction);
+        let record = self.record(t, &ray);
+
+        Some(LightSample {
+            direction,
+            distance: t,
+            pdf:      1.0 / (2.0 * std::f32::consts::PI * one_minus_cos_max),
+            radiance: self.material.emitted(&ray, &record),
+        })
+    }
+
+    fn light_pdf(&self, origin: Vec3, _record: &HitRecord) -> f32 {
+        match self.cone(origin) {
+            Some(one_minus_cos_max) => 1.0 / (2.0 * std::f32::consts::PI * one_minus_cos_max),
+            None                    => 0.0,
+        }
+    }
 }
@@ -0,0 +1,159 @@
+use super::{HitRecord, Traceable, TriangleMesh, LightSample};
+use crate::{Vec3, Ray};
+use crate::math::AABB;
+use crate::rng::Rng;
+
+use std::sync::Arc;
+
+pub struct Triangle {
+    mesh:  Arc<TriangleMesh>,
+    index: u32,
+}
+
+impl Triangle {
+    pub fn new(mesh: &Arc<TriangleMesh>, index: u32) -> Self {
+        assert!((index as usize) < mesh.triangle_count(), "Triangle index is out of bounds.");
+
+        Self {
+            mesh: mesh.clone(),
+            index,
+        }
+    }
+
+    #[inline(always)]
+    fn positions(&self) -> (Vec3, Vec3, Vec3) {
+        let [a, b, c] = self.mesh.vertices(self.index);
+
+        (self.mesh.position(a), self.mesh.position(b), self.mesh.position(c))
+    }
+
+    /// Returns unnormalized geometric normal, its length is twice the triangle area.
+    #[inline(always)]
+    fn geometric_normal(&self) -> Vec3 {
+        let (p0, p1, p2) = self.positions();
+
+        Vec3::cross(p1 - p0, p2 - p0)
+    }
+
+    /// Creates hit record for point with barycentric coordinates `u` (weight of the second
+    /// vertex) and `v` (weight of the third vertex).
+    fn record(&self, t: f32, point: Vec3, u: f32, v: f32) -> HitRecord<'_> {
+        let w = 1.0 - u - v;
+
+        let [a, b, c] = self.mesh.vertices(self.index);
+
+        let normal = match (self.mesh.normal(a), self.mesh.normal(b), self.mesh.normal(c)) {
+            (Some(n0), Some(n1), Some(n2)) => (n0 * w + n1 * u + n2 * v).normalized(),
+            _                              => self.geometric_normal().normalized(),
+        };
+
+        let uv = match (self.mesh.uv(a), self.mesh.uv(b), self.mesh.uv(c)) {
+            (Some(uv0), Some(uv1), Some(uv2)) => (
+                uv0.0 * w + uv1.0 * u + uv2.0 * v,
+                uv0.1 * w + uv1.1 * u + uv2.1 * v,
+            ),
+            _ => (u, v),
+        };
+
+        let mut record = HitRecord::with_uv(t, point, normal, &**self.mesh.material(), uv);
+
+        record.object = Some(self);
+        record
+    }
+
+    /// Returns ray parameter and barycentric coordinates of the intersection with `ray` if it
+    /// lies between `min_t` and `max_t`.
+    #[inline(always)]
+    fn intersect(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<(f32, f32, f32)> {
+        const EPSILON: f32 = 1e-8;
+
+        // Möller–Trumbore intersection.
+        let (p0, p1, p2) = self.positions();
+
+        let edge1 = p1 - p0;
+        let edge2 = p2 - p0;
+
+        let h   = Vec3::cross(ray.direction, edge2);
+        let det = Vec3::dot(edge1, h);
+
+        if det.abs() < EPSILON {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+
+        let s = ray.origin - p0;
+        let u = Vec3::dot(s, h) * inv_det;
+
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = Vec3::cross(s, edge1);
+        let v = Vec3::dot(ray.direction, q) * inv_det;
+
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = Vec3::dot(edge2, q) * inv_det;
+
+        if t >= max_t || t <= min_t {
+            return None;
+        }
+
+        Some((t, u, v))
+    }
+
+    /// Converts area PDF of uniformly sampling the triangle to solid angle PDF.
+    fn solid_angle_pdf(&self, origin: Vec3, point: Vec3) -> f32 {
+        let normal = self.geometric_normal();
+
+        super::area_to_solid_angle_pdf(origin, point, normal, normal.length() * 0.5)
+    }
+}
+
+impl Traceable for Triangle {
+    fn trace(&self, ray: &Ray, min_t: f32, max_t: f32) -> Option<HitRecord<'_>> {
+        let (t, u, v) = self.intersect(ray, min_t, max_t)?;
+
+        Some(self.record(t, ray.point(t), u, v))
+    }
+
+    fn occluded(&self, ray: &Ray, min_t: f32, max_t: f32) -> bool {
+        self.intersect(ray, min_t, max_t).is_some()
+    }
+
+    fn bounding_box(&self) -> AABB {
+        let (p0, p1, p2) = self.positions();
+
+        let min = Vec3::min(p0, Vec3::min(p1, p2));
+        let max = Vec3::max(p0, Vec3::max(p1, p2));
+
+        AABB::new(min - Vec3::fill(super::FLAT_PADDING), max + Vec3::fill(super::FLAT_PADDING))
+    }
+
+    fn is_light(&self) -> bool {
+        self.mesh.material().is_emissive()
+    }
+
+    fn sample_light(&self, origin: Vec3, rng: &mut Rng) -> Option<LightSample> {
+        // Uniformly sample the triangle area.
+        let su = rng.rand::<f32>().sqrt();
+        let u  = su * (1.0 - rng.rand::<f32>());
+        let v  = su - u;
+
+        let (p0, p1, p2) = self.positions();
+
+        let point  = p0 * (1.0 - su) + p1 * u + p2 * v;
+        let normal = self.geometric_normal();
+
+        super::sample_flat_light(origin, point, normal, normal.length() * 0.5, |ray, distance| {
+            self.mesh.material().emitted(ray, &self.record(distance, point, u, v))
+        })
+    }
+
+    fn light_pdf(&self, origin: Vec3, record: &HitRecord) -> f32 {
+        self.solid_angle_pdf(origin, record.point)
+    }
+}
@@ -0,0 +1,176 @@
+//! Acceleration structures must find exactly the same intersections whatever their layout.
+
+use path_tracer::{Vec3, Ray};
+use path_tracer::material::{Lambertian, SharedMaterial};
+use path_tracer::math::Mat4;
+use path_tracer::rng::Rng;
+use path_tracer::scene::{Scene, Blas, BvhSettings, BvhWidth};
+use path_tracer::traceable::{HitRecord, Sphere, TriangleMesh, Instance};
+
+use std::sync::Arc;
+
+const WIDTHS:  [BvhWidth; 3] = [BvhWidth::Two, BvhWidth::Four, BvhWidth::Eight];
+const RAYS:    usize         = 4000;
+const SPHERES: usize         = 300;
+
+fn random_point(rng: &mut Rng, extent: f32) -> Vec3 {
+    Vec3::new(rng.rand_range(-extent, extent), rng.rand_range(-extent, extent),
+              rng.rand_range(-extent, extent))
+}
+
+/// One material per sphere and one for the mesh, so that hits on different objects can be
+/// told apart.
+fn materials() -> Vec<SharedMaterial> {
+    (0..SPHERES + 1).map(|_| Lambertian::new_solid(Vec3::fill(0.5))).collect()
+}
+
+/// Random spheres and a mesh of random triangles using `materials`.
+fn random_geometry(seed: u64, materials: &[SharedMaterial]) -> (Vec<Sphere>, Arc<TriangleMesh>) {
+    let mut rng = Rng::with_seed(seed);
+
+    let spheres = materials[..SPHERES].iter()
+        .map(|material| {
+            Sphere::new(random_point(&mut rng, 10.0), rng.rand_range(0.05, 0.6), material)
+        })
+        .collect();
+
+    let mut positions = Vec::new();
+    let mut indices   = Vec::new();
+
+    for i in 0..2000 {
+        let center = random_point(&mut rng, 10.0);
+
+        for _ in 0..3 {
+            positions.push(center + random_point(&mut rng, 0.5));
+        }
+
+        indices.push([i * 3, i * 3 + 1, i * 3 + 2]);
+    }
+
+    (spheres, TriangleMesh::new(positions, None, None, indices, &materials[SPHERES]))
+}
+
+fn settings(width: BvhWidth) -> BvhSettings {
+    BvhSettings {
+        width,
+        ..BvhSettings::default()
+    }
+}
+
+fn scene(width: BvhWidth, seed: u64, materials: &[SharedMaterial]) -> Scene {
+    let (spheres, mesh) = random_geometry(seed, materials);
+
+    let mut scene = Scene::new();
+
+    scene.set_bvh_settings(settings(width));
+
+    for sphere in spheres {
+        scene.add(sphere);
+    }
+
+    scene.add_mesh(&mesh);
+    scene.construct_bvh();
+
+    scene
+}
+
+/// Ray from a random point towards another one, so that most rays cross the geometry.
+fn random_ray(rng: &mut Rng) -> Ray {
+    let origin = random_point(rng, 12.0);
+
+    Ray::new(origin, random_point(rng, 8.0) - origin)
+}
+
+/// Everything identifying a hit, compared bit for bit.
+fn hit_key(record: &HitRecord) -> ([u32; 7], *const ()) {
+    let [px, py, pz] = record.point.extract_array();
+    let [nx, ny, nz] = record.normal.extract_array();
+
+    let bits = [record.t, px, py, pz, nx, ny, nz].map(f32::to_bits);
+
+    (bits, record.material as *const _ as *const ())
+}
+
+#[test]
+fn widths_find_identical_hits() {
+    let materials = materials();
+
+    let scenes: Vec<Scene> = WIDTHS.iter()
+        .map(|&width| scene(width, 7, &materials))
+        .collect();
+
+    let mut rng  = Rng::with_seed(11);
+    let mut hits = 0;
+
+    for _ in 0..RAYS {
+        let ray = random_ray(&mut rng);
+
+        let keys: Vec<_> = scenes.iter()
+            .map(|scene| scene.trace(&ray).as_ref().map(hit_key))
+            .collect();
+
+        assert!(keys.iter().all(|key| *key == keys[0]),
+                "BVH widths disagree on ray from {:?} towards {:?}.",
+                ray.origin.extract(), ray.direction.extract());
+
+        hits += keys[0].is_some() as usize;
+    }
+
+    // Make sure the rays actually test something.
+    assert!(hits > RAYS / 4, "Only {} of {} rays hit anything.", hits, RAYS);
+}
+
+/// Adds transformed copies of a BLAS over random geometry to an already constructed scene.
+fn add_instances(scene: &mut Scene, width: BvhWidth, materials: &[SharedMaterial]) {
+    let (_, mesh) = random_geometry(13, materials);
+    let blas      = Arc::new(Blas::from_meshes(&[mesh], &settings(width)));
+
+    let transforms = [
+        Mat4::identity(),
+        Mat4::translation(Vec3::new(3.0, -2.0, 1.0)) * Mat4::scale(Vec3::new(0.5, 1.5, 0.8)),
+        Mat4::rotation(Vec3::new(1.0, 1.0, 0.0).normalized(), 40.0) *
+            Mat4::scale(Vec3::fill(0.7)),
+    ];
+
+    for &transform in &transforms {
+        scene.add_instance(Instance::new(blas.clone(), transform));
+    }
+
+    scene.rebuild_tlas();
+}
+
+#[test]
+fn occlusion_matches_closest_hit() {
+    let materials = materials();
+
+    for &width in &WIDTHS {
+        for &instanced in &[false, true] {
+            let mut scene = scene(width, 7, &materials);
+
+            if instanced {
+                add_instances(&mut scene, width, &materials);
+            }
+
+            let mut rng      = Rng::with_seed(17);
+            let mut occluded = 0;
+
+            for _ in 0..RAYS {
+                let ray   = random_ray(&mut rng);
+                let max_t = rng.rand_range(0.01, 30.0);
+
+                let expected = scene.trace_until(&ray, max_t).is_some();
+
+                assert_eq!(scene.occluded(&ray, max_t), expected,
+                           "Occlusion with {:?} BVH (instanced: {}) disagrees on ray from {:?} \
+                            towards {:?} up to {}.", width, instanced, ray.origin.extract(),
+                           ray.direction.extract(), max_t);
+
+                occluded += expected as usize;
+            }
+
+            // Both outcomes must be common for the comparison to mean anything.
+            assert!(occluded > RAYS / 10 && occluded < RAYS * 9 / 10,
+                    "{} of {} segments are occluded.", occluded, RAYS);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Interrupted renders continued from a checkpoint must give exactly the same image.
+
+use path_tracer::checkpoint::Checkpoint;
+use path_tracer::parallel_renderer::ParallelRenderer;
+use path_tracer::raytracer::{Raytracer, Pixel};
+use path_tracer::scene::{Scene, generators};
+use path_tracer::rng::Rng;
+
+const WIDTH:   usize = 24;
+const HEIGHT:  usize = 16;
+const SAMPLES: usize = 4;
+const SEED:    u64   = 5;
+
+fn raytracer(spectral: bool) -> Raytracer {
+    let mut scene = Scene::new();
+
+    let camera = generators::cornell_box(&mut scene, &mut Rng::with_seed(1));
+
+    let mut raytracer = Raytracer::new(camera.camera(WIDTH, HEIGHT), scene, SAMPLES, SEED);
+
+    raytracer.set_spectral(spectral);
+    raytracer
+}
+
+fn bits(buffer: &[Pixel]) -> Vec<u32> {
+    buffer.iter().flatten().map(|channel| channel.to_bits()).collect()
+}
+
+#[test]
+fn resumed_render_matches_uninterrupted_one() {
+    let path = std::env::temp_dir().join(format!("path-tracer-{}.checkpoint",
+                                                 std::process::id()));
+
+    for &spectral in &[false, true] {
+        let expected = raytracer(spectral).render_image();
+
+        for interrupted in 1..SAMPLES {
+            // Render until the interruption and save the checkpoint the way the CLI does.
+            {
+                let raytracer    = raytracer(spectral);
+                let mut renderer = ParallelRenderer::new();
+                let mut buffer   = vec![Pixel::default(); raytracer.pixel_count()];
+
+                raytracer.render(&mut renderer, &mut buffer, 0..interrupted, |passes, buffer| {
+                    let checkpoint = Checkpoint {
+                        width:       WIDTH,
+                        height:      HEIGHT,
+                        samples:     SAMPLES,
+                        passes_done: passes,
+                        seed:        SEED,
+                        spectral,
+                        inputs:      0,
+                    };
+
+                    checkpoint.save(&path, buffer).unwrap();
+                });
+            }
+
+            let (checkpoint, mut buffer) = Checkpoint::load(&path).unwrap();
+
+            assert_eq!(checkpoint.passes_done, interrupted);
+            assert_eq!(checkpoint.spectral, spectral);
+
+            let raytracer    = raytracer(spectral);
+            let mut renderer = ParallelRenderer::new();
+
+            raytracer.render(&mut renderer, &mut buffer, checkpoint.passes_done..SAMPLES,
+                             |_, _| {});
+
+            assert!(bits(&buffer) == bits(&expected),
+                    "Render resumed after {} of {} passes (spectral: {}) differs.", interrupted,
+                    SAMPLES, spectral);
+        }
+    }
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,134 @@
+//! Scene files and the files they reference.
+
+use path_tracer::Vec3;
+use path_tracer::material::Lambertian;
+use path_tracer::scene::loader::{self, LoadError};
+use path_tracer::scene::obj;
+use path_tracer::traceable::{Traceable, TriangleMesh};
+
+use std::path::PathBuf;
+
+/// Writes `contents` to a file in a directory private to this test process, so that scene
+/// files can reference it by a relative path.
+fn write_file(name: &str, contents: &[u8]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("path-tracer-{}", std::process::id()));
+
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let path = directory.join(name);
+
+    std::fs::write(&path, contents).unwrap();
+
+    path
+}
+
+/// Parses scene `source` as if it was stored next to the files written by `write_file` and
+/// returns the position and message of the syntax error it must have.
+fn syntax_error(source: &str) -> (usize, usize, String) {
+    let path = write_file("scene.scene", source.as_bytes());
+
+    match loader::parse(source, &path) {
+        Err(LoadError::Syntax(_, position, message)) => (position.line, position.column, message),
+        Err(err) => panic!("Expected syntax error, got `{}`.", err),
+        Ok(..)   => panic!("Expected syntax error, the scene loaded."),
+    }
+}
+
+#[test]
+fn syntax_errors_point_at_their_cause() {
+    let cases = [
+        ("camera { fov = 40 }\nmaterial red { type = lambertian color = (1, 0 }",
+         (2, 48), "expected `,`, found `}`"),
+        ("camera { fov = 40\nsphere { radius = 1 }", (2, 8), "expected `=`, found `{`"),
+        ("material m { type = lambertian color = 0.5 }\n\
+          sphere { center = (0, 0, 0) radius = 1 material = m colour = 1 }",
+         (2, 53), "unknown property `colour`"),
+        ("camera { fov = 4e }", (1, 16), "invalid number `4e`"),
+    ];
+
+    for &(source, position, expected) in &cases {
+        let (line, column, message) = syntax_error(source);
+
+        assert_eq!((line, column), position, "Wrong position of `{}`.", message);
+        assert!(message.contains(expected), "Expected `{}`, got `{}`.", expected, message);
+    }
+}
+
+#[test]
+fn empty_environment_map_is_rejected() {
+    write_file("empty.hdr", b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 0 +X 0\n");
+
+    let (line, column, message) = syntax_error("camera { fov = 40 }\n\
+                                                environment { type = map path = \"empty.hdr\" }");
+
+    assert_eq!((line, column), (2, 26));
+    assert!(message.contains("image is empty"), "Unexpected message `{}`.", message);
+}
+
+#[test]
+fn obj_names_can_contain_hashes() {
+    write_file("hash#1.mtl", b"newmtl red#1 # Material name contains a hash.\nKd 0.8 0.1 0.1\n");
+
+    let path = write_file("hash.obj", b"# Comment line.\n\
+                                        mtllib hash#1.mtl\n\
+                                        v 0 0 0\nv 1 0 0\nv 0 1 0 #comment\n\
+                                        usemtl red#1\n\
+                                        f 1 2 3 # comment\n");
+
+    let meshes = obj::load(&path, None).unwrap_or_else(|err| panic!("{}", err));
+
+    assert_eq!(meshes.len(), 1);
+    assert_eq!(meshes[0].triangle_count(), 1);
+}
+
+#[test]
+fn obj_material_override_skips_mtl_files() {
+    let path = write_file("override.obj", b"mtllib missing.mtl\n\
+                                            v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\n\
+                                            usemtl first\nf 1 2 3\n\
+                                            usemtl second\nf 2 4 3\n");
+
+    let material = Lambertian::new_solid(Vec3::fill(0.5));
+    let meshes   = obj::load(&path, Some(&material)).unwrap_or_else(|err| panic!("{}", err));
+
+    assert_eq!(meshes.len(), 1, "Faces with the overridden material must share a mesh.");
+    assert_eq!(meshes[0].triangle_count(), 2);
+
+    assert!(obj::load(&path, None).is_err(), "Missing MTL file must fail without override.");
+}
+
+#[test]
+fn obj_polygons_are_fan_triangulated() {
+    // The pentagon references its vertices relative to the end of the vertex list.
+    let path = write_file("polygons.obj", b"v 5 5 5\nv 6 5 5\nv 5 6 5\nf 1 2 3\n\
+                                            v 0 0 0\nv 2 0 0\nv 3 2 0\nv 1 4 0\nv -1 2 0\n\
+                                            f -5 -4 -3 -2 -1\n");
+
+    let meshes = obj::load(&path, None).unwrap_or_else(|err| panic!("{}", err));
+
+    assert_eq!(meshes.len(), 1);
+
+    let expected = [
+        ((5.0, 5.0, 5.0), (6.0, 6.0, 5.0)),
+        ((0.0, 0.0, 0.0), (3.0, 2.0, 0.0)),
+        ((0.0, 0.0, 0.0), (3.0, 4.0, 0.0)),
+        ((-1.0, 0.0, 0.0), (1.0, 4.0, 0.0)),
+    ];
+
+    let triangles: Vec<_> = TriangleMesh::triangles(&meshes[0]).collect();
+
+    assert_eq!(triangles.len(), expected.len());
+
+    for (triangle, &(min, max)) in triangles.iter().zip(&expected) {
+        let bbox  = triangle.bounding_box();
+        let error = |actual: Vec3, (x, y, z)| {
+            let (dx, dy, dz) = (actual - Vec3::new(x, y, z)).extract();
+
+            dx.abs().max(dy.abs()).max(dz.abs())
+        };
+
+        assert!(error(bbox.min, min) < 1e-3 && error(bbox.max, max) < 1e-3,
+                "Triangle spans {:?} to {:?}, expected {:?} to {:?}.", bbox.min.extract(),
+                bbox.max.extract(), min, max);
+    }
+}
@@ -0,0 +1,116 @@
+//! Sampled scattering must agree with evaluated BSDFs, otherwise combining BSDF and light
+//! sampling gives biased images.
+
+use path_tracer::{Vec3, Ray};
+use path_tracer::material::{SharedMaterial, RoughConductor, Conductor, RoughDielectric};
+use path_tracer::rng::Rng;
+use path_tracer::traceable::HitRecord;
+
+const SAMPLES: usize = 20000;
+
+/// Relative error allowed between the two weights. The default approximate normalization
+/// makes them differ slightly at grazing angles.
+const TOLERANCE: f32 = if cfg!(feature = "precise") { 2e-3 } else { 1e-2 };
+
+/// Directions towards the ray origin, from normal to grazing incidence.
+fn outgoing_directions() -> Vec<Vec3> {
+    [0.0f32, 30.0, 60.0, 85.0].iter()
+        .map(|angle| {
+            let (sin, cos) = angle.to_radians().sin_cos();
+
+            Vec3::new(sin, 0.2 * sin, cos).normalized()
+        })
+        .collect()
+}
+
+/// Number of scattered rays on the same side of the surface as the incoming ray and on the
+/// opposite side.
+struct Lobes {
+    reflected:   usize,
+    transmitted: usize,
+}
+
+/// Checks that the weight returned by `scatter` equals BSDF divided by PDF returned by
+/// `evaluate` for the same direction.
+fn check_consistency(material: &SharedMaterial, wo: Vec3) -> Lobes {
+    let normal = Vec3::new(0.0, 0.0, 1.0);
+    let ray    = Ray::new(wo * 2.0, -wo);
+    let record = HitRecord::new(2.0, Vec3::zero(), normal, &**material, |_| (0.0, 0.0));
+
+    let mut rng   = Rng::with_seed(3);
+    let mut lobes = Lobes {
+        reflected:   0,
+        transmitted: 0,
+    };
+
+    for _ in 0..SAMPLES {
+        let (weight, scattered) = match material.scatter(&ray, &record, &mut rng) {
+            Some(scattered) => scattered,
+            None            => continue,
+        };
+
+        let direction   = scattered.direction;
+        let (bsdf, pdf) = material.evaluate(&ray, &record, direction)
+            .expect("Rough materials must be evaluable.");
+
+        assert!(pdf > 0.0, "Sampled direction {:?} has zero PDF.", direction.extract());
+
+        let expected = bsdf / pdf;
+
+        for (&a, &e) in weight.extract_array().iter().zip(&expected.extract_array()) {
+            assert!((a - e).abs() <= TOLERANCE * e.abs().max(1.0),
+                    "Scatter weight {:?} doesn't match BSDF / PDF {:?} for direction {:?} \
+                     scattered from {:?}.", weight.extract(), expected.extract(),
+                    direction.extract(), wo.extract());
+        }
+
+        if (Vec3::dot(direction, normal) > 0.0) == (Vec3::dot(wo, normal) > 0.0) {
+            lobes.reflected += 1;
+        } else {
+            lobes.transmitted += 1;
+        }
+    }
+
+    lobes
+}
+
+#[test]
+fn rough_conductor_scatter_matches_evaluate() {
+    for &roughness in &[0.05, 0.3, 0.7, 1.0] {
+        let material = RoughConductor::preset(Conductor::Gold, roughness);
+
+        for wo in outgoing_directions() {
+            let lobes = check_consistency(&material, wo);
+
+            // Very rough surfaces discard up to half of the samples below the horizon.
+            assert_eq!(lobes.transmitted, 0);
+            assert!(lobes.reflected > SAMPLES / 4, "Only {} of {} samples reflected.",
+                    lobes.reflected, SAMPLES);
+        }
+    }
+}
+
+#[test]
+fn rough_dielectric_scatter_matches_evaluate() {
+    for &roughness in &[0.05, 0.3, 0.7, 1.0] {
+        let material = RoughDielectric::new_solid(1.5, roughness);
+
+        // Rays entering the medium and leaving it.
+        for &side in &[1.0, -1.0] {
+            let (mut reflected, mut transmitted) = (0, 0);
+
+            for wo in outgoing_directions() {
+                let lobes = check_consistency(&material, wo * side);
+
+                reflected   += lobes.reflected;
+                transmitted += lobes.transmitted;
+            }
+
+            // Rays leaving the medium are mostly reflected internally at grazing angles, but
+            // both lobes must be sampled often from either side.
+            assert!(reflected > SAMPLES / 10 && transmitted > SAMPLES / 10,
+                    "Reflected {} and transmitted {} samples from side {}.", reflected,
+                    transmitted, side);
+        }
+    }
+}
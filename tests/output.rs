@@ -0,0 +1,183 @@
+//! High dynamic range outputs must store the rendered radiance exactly.
+
+use path_tracer::output::{self, DisplayTransform};
+use path_tracer::raytracer::Pixel;
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::path::PathBuf;
+
+const WIDTH:  usize = 5;
+const HEIGHT: usize = 3;
+
+/// Pixels spanning many orders of magnitude, different in every channel and position so that
+/// swapped channels or flipped rows are detected.
+fn hdr_buffer() -> Vec<Pixel> {
+    (0..WIDTH * HEIGHT)
+        .map(|index| {
+            let value = 10f32.powi(index as i32 - 7) * 1.2345;
+
+            [value, value * 3.0 + 0.5, 65504.0 - index as f32]
+        })
+        .collect()
+}
+
+fn save(name: &str, buffer: &[Pixel]) -> Vec<u8> {
+    let directory = std::env::temp_dir().join(format!("path-tracer-{}", std::process::id()));
+
+    std::fs::create_dir_all(&directory).unwrap();
+
+    let path: PathBuf = directory.join(name);
+
+    output::save(&path, WIDTH, HEIGHT, buffer, &DisplayTransform::default()).unwrap();
+
+    std::fs::read(path).unwrap()
+}
+
+fn f32_at(bytes: &[u8], offset: usize) -> f32 {
+    f32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn i32_at(bytes: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// Splits null terminated string from the start of `bytes`.
+fn string(bytes: &[u8]) -> (&str, &[u8]) {
+    let end = bytes.iter().position(|&byte| byte == 0).unwrap();
+
+    (std::str::from_utf8(&bytes[..end]).unwrap(), &bytes[end + 1..])
+}
+
+/// Reads little endian portable float map.
+fn read_pfm(bytes: &[u8]) -> (usize, usize, Vec<Pixel>) {
+    let mut lines = bytes.splitn(4, |&byte| byte == b'\n');
+    let mut line  = || std::str::from_utf8(lines.next().unwrap()).unwrap();
+
+    assert_eq!(line(), "PF");
+
+    let size: Vec<usize> = line().split(' ').map(|value| value.parse().unwrap()).collect();
+    let scale: f32       = line().parse().unwrap();
+
+    assert!(scale < 0.0, "PFM data must be little endian.");
+
+    let (width, height) = (size[0], size[1]);
+    let data            = lines.next().unwrap();
+
+    assert_eq!(data.len(), width * height * 12);
+
+    // Scanlines are stored from the bottom one.
+    let pixels = (0..height).rev()
+        .flat_map(|y| (0..width).map(move |x| (y * width + x) * 12))
+        .map(|offset| [f32_at(data, offset), f32_at(data, offset + 4), f32_at(data, offset + 8)])
+        .collect();
+
+    (width, height, pixels)
+}
+
+/// Reads single part scanline OpenEXR image with uncompressed 32 bit float R, G and B
+/// channels.
+fn read_exr(bytes: &[u8]) -> (usize, usize, Vec<Pixel>) {
+    assert_eq!(i32_at(bytes, 0), 20000630, "Wrong EXR magic number.");
+    assert_eq!(i32_at(bytes, 4), 2, "EXR must be a version 2 single part scanline image.");
+
+    let mut attributes = HashMap::new();
+    let mut rest       = &bytes[8..];
+
+    loop {
+        let (name, after) = string(rest);
+
+        if name.is_empty() {
+            rest = after;
+            break;
+        }
+
+        let (kind, after) = string(after);
+        let size          = i32_at(after, 0) as usize;
+
+        attributes.insert(name, (kind, &after[4..4 + size]));
+        rest = &after[4 + size..];
+    }
+
+    for required in &["channels", "compression", "dataWindow", "displayWindow", "lineOrder",
+                      "pixelAspectRatio", "screenWindowCenter", "screenWindowWidth"] {
+        assert!(attributes.contains_key(required), "EXR lacks `{}` attribute.", required);
+    }
+
+    assert_eq!(attributes["compression"], ("compression", &[0u8][..]));
+
+    let (kind, window) = attributes["dataWindow"];
+
+    assert_eq!(kind, "box2i");
+    assert_eq!((i32_at(window, 0), i32_at(window, 4)), (0, 0));
+
+    let width  = i32_at(window, 8) as usize + 1;
+    let height = i32_at(window, 12) as usize + 1;
+
+    // Channel list entries are the name, pixel type, linearity with padding and sampling.
+    let (kind, mut list) = attributes["channels"];
+    let mut channels     = Vec::new();
+
+    assert_eq!(kind, "chlist");
+
+    loop {
+        let (name, after) = string(list);
+
+        if name.is_empty() {
+            break;
+        }
+
+        assert_eq!(i32_at(after, 0), 2, "Channel `{}` isn't 32 bit float.", name);
+        assert_eq!((i32_at(after, 8), i32_at(after, 12)), (1, 1));
+
+        channels.push(name);
+        list = &after[16..];
+    }
+
+    assert_eq!(channels, ["B", "G", "R"], "Channels must be sorted by name.");
+
+    let mut pixels = vec![[0.0; 3]; width * height];
+
+    for y in 0..height {
+        let offset = u64::from_le_bytes(rest[y * 8..y * 8 + 8].try_into().unwrap()) as usize;
+        let chunk  = &bytes[offset..];
+
+        assert_eq!(i32_at(chunk, 0) as usize, y);
+        assert_eq!(i32_at(chunk, 4) as usize, width * channels.len() * 4);
+
+        for (index, name) in channels.iter().enumerate() {
+            let channel = ["R", "G", "B"].iter().position(|other| other == name).unwrap();
+
+            for x in 0..width {
+                pixels[y * width + x][channel] = f32_at(chunk, 8 + (index * width + x) * 4);
+            }
+        }
+    }
+
+    (width, height, pixels)
+}
+
+fn assert_round_trip((width, height, pixels): (usize, usize, Vec<Pixel>), expected: &[Pixel]) {
+    assert_eq!((width, height), (WIDTH, HEIGHT));
+
+    // Values must be bit for bit identical.
+    let bits = |pixels: &[Pixel]| -> Vec<u32> {
+        pixels.iter().flatten().map(|channel| channel.to_bits()).collect()
+    };
+
+    assert_eq!(bits(&pixels), bits(expected));
+}
+
+#[test]
+fn pfm_round_trip() {
+    let buffer = hdr_buffer();
+
+    assert_round_trip(read_pfm(&save("round_trip.pfm", &buffer)), &buffer);
+}
+
+#[test]
+fn exr_round_trip() {
+    let buffer = hdr_buffer();
+
+    assert_round_trip(read_exr(&save("round_trip.exr", &buffer)), &buffer);
+}
@@ -0,0 +1,63 @@
+//! Scene construction.
+
+use path_tracer::{Vec3, Ray};
+use path_tracer::material::{Lambertian, DiffuseLight};
+use path_tracer::raytracer::Raytracer;
+use path_tracer::scene::{Scene, generators};
+use path_tracer::traceable::Sphere;
+use path_tracer::rng::Rng;
+
+#[test]
+fn construct_bvh_twice_keeps_lights() {
+    let mut scene = Scene::new();
+
+    let light = DiffuseLight::new(Vec3::fill(4.0));
+    let white = Lambertian::new_solid(Vec3::fill(0.5));
+
+    scene.add(Sphere::new(Vec3::new(0.0, 2.0, 0.0), 0.5, &light));
+    scene.add(Sphere::new(Vec3::new(2.0, 2.0, 0.0), 0.5, &light));
+    scene.add(Sphere::new(Vec3::zero(), 1.0, &white));
+
+    scene.construct_bvh();
+    scene.construct_bvh();
+
+    assert_eq!(scene.light_count(), 2);
+    assert_eq!(scene.light_selection_pdf(), 0.5);
+}
+
+#[test]
+fn objects_added_after_construct_bvh_keep_earlier_ones() {
+    let mut scene = Scene::new();
+
+    let white = Lambertian::new_solid(Vec3::fill(0.5));
+
+    scene.add(Sphere::new(Vec3::new(-2.0, 0.0, 0.0), 1.0, &white));
+    scene.construct_bvh();
+
+    scene.add(Sphere::new(Vec3::new(2.0, 0.0, 0.0), 1.0, &white));
+    scene.construct_bvh();
+
+    assert_eq!(scene.object_count(), 2);
+
+    for &x in &[-2.0, 2.0] {
+        let ray = Ray::new(Vec3::new(x, 0.0, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        let hit = scene.trace(&ray).unwrap_or_else(|| panic!("Sphere at {} is missed.", x));
+
+        assert!((hit.t - 4.0).abs() < 1e-3, "Sphere at {} hit at distance {}.", x, hit.t);
+        assert!(scene.occluded(&ray, 10.0), "Sphere at {} doesn't occlude.", x);
+    }
+}
+
+#[test]
+fn raytracer_keeps_lights_of_constructed_scene() {
+    let mut scene = Scene::new();
+    let settings  = generators::cornell_box(&mut scene, &mut Rng::with_seed(1));
+
+    scene.construct_bvh();
+
+    let lights     = scene.light_count();
+    let mut tracer = Raytracer::new(settings.camera(8, 8), scene, 1, 1);
+
+    assert!(lights > 0, "Cornell box has no lights.");
+    assert_eq!(tracer.scene_mut().light_count(), lights);
+}
@@ -0,0 +1,83 @@
+//! Dispersion and conversion between spectra and RGB.
+
+use path_tracer::Vec3;
+use path_tracer::material::Ior;
+use path_tracer::spectrum::{self, SpectralResponse};
+
+/// Schott N-BK7 glass.
+const BK7: Ior = Ior::Sellmeier([1.039_612, 0.231_792_3, 1.010_469],
+                                [0.006_000_699, 0.020_017_91, 103.560_6]);
+
+/// Averages `f` over uniformly spaced wavelengths of the sampled spectrum.
+fn average(f: impl Fn(f32) -> Vec3) -> Vec3 {
+    const STEPS: usize = 4000;
+
+    let sum = (0..STEPS)
+        .map(|i| f(spectrum::wavelength((i as f32 + 0.5) / STEPS as f32)))
+        .fold(Vec3::zero(), |sum, value| sum + value);
+
+    sum / STEPS as f32
+}
+
+fn assert_close(actual: Vec3, expected: Vec3, tolerance: f32) {
+    let (x, y, z) = (actual - expected).extract();
+
+    assert!(x.abs().max(y.abs()).max(z.abs()) < tolerance,
+            "Expected {:?}, got {:?}.", expected.extract(), actual.extract());
+}
+
+#[test]
+fn sellmeier_matches_bk7() {
+    // Refractive indices of N-BK7 from the Schott catalogue.
+    let known = [(486.13, 1.522_38), (Ior::REFERENCE_WAVELENGTH, 1.516_80), (656.27, 1.514_32)];
+
+    for &(wavelength, n) in &known {
+        assert!((BK7.at(wavelength) - n).abs() < 1e-4,
+                "BK7 index at {} nm is {}, expected {}.", wavelength, BK7.at(wavelength), n);
+    }
+
+    assert!(BK7.is_valid());
+    assert!(BK7.is_dispersive());
+}
+
+#[test]
+fn cauchy_decreases_with_wavelength() {
+    let flint = Ior::Cauchy([1.67, 0.0743, 0.0]);
+
+    assert!(flint.at(spectrum::MIN_WAVELENGTH) > flint.at(spectrum::MAX_WAVELENGTH));
+    assert!(!Ior::Constant(1.5).is_dispersive());
+}
+
+#[test]
+fn sellmeier_pole_is_invalid() {
+    // Resonance at 500 nm makes the index infinite and then imaginary.
+    let pole = Ior::Sellmeier([1.0, 0.0, 0.0], [0.25, 0.0, 0.0]);
+
+    assert!(!pole.is_valid());
+}
+
+#[test]
+fn equal_energy_spectrum_is_white() {
+    let response = SpectralResponse::new();
+
+    assert_close(average(|wavelength| response.weight(wavelength)), Vec3::fill(1.0), 1e-3);
+}
+
+#[test]
+fn uplift_round_trips_colors() {
+    let response = SpectralResponse::new();
+
+    for &color in &[Vec3::fill(1.0), Vec3::fill(0.25), Vec3::new(0.8, 0.3, 0.1),
+                    Vec3::new(0.2, 0.6, 0.9)] {
+        let converted = average(|wavelength| {
+            response.weight(wavelength) * response.uplift(color, wavelength)
+        });
+
+        assert_close(converted, color, 2e-3);
+    }
+
+    // Gray colors have flat spectra.
+    for &wavelength in &[400.0, 500.0, 600.0, 700.0] {
+        assert!((response.uplift(Vec3::fill(0.5), wavelength) - 0.5).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,78 @@
+//! Intersections of flat primitives and boxes.
+
+use path_tracer::{Vec3, Ray};
+use path_tracer::material::Lambertian;
+use path_tracer::rng::Rng;
+use path_tracer::traceable::{Traceable, Plane, Quad, Disc, Cuboid};
+
+fn random_vector(rng: &mut Rng, extent: f32) -> Vec3 {
+    Vec3::new(rng.rand_range(-extent, extent), rng.rand_range(-extent, extent),
+              rng.rand_range(-extent, extent))
+}
+
+#[test]
+fn occluded_matches_trace() {
+    let material = Lambertian::new_solid(Vec3::fill(0.5));
+
+    let objects: [(&str, Box<dyn Traceable>); 4] = [
+        ("plane", Box::new(Plane::new(Vec3::new(0.0, -0.5, 0.0), Vec3::new(0.2, 1.0, 0.1),
+                                      &material))),
+        ("quad", Box::new(Quad::new(Vec3::new(-1.0, -1.0, 0.3), Vec3::new(2.0, 0.5, 0.0),
+                                    Vec3::new(0.0, 1.5, 1.0), &material))),
+        ("disc", Box::new(Disc::new(Vec3::zero(), Vec3::new(1.0, 1.0, 0.0), 1.5, &material))),
+        ("box", Box::new(Cuboid::oriented(Vec3::zero(), Vec3::new(2.0, 1.0, 1.5),
+                                          Vec3::new(1.0, 1.0, 0.0), Vec3::new(0.0, 1.0, 0.0),
+                                          &material))),
+    ];
+
+    let mut rng = Rng::with_seed(3);
+
+    for (name, object) in &objects {
+        let mut hits = 0;
+
+        for _ in 0..2000 {
+            let origin = random_vector(&mut rng, 4.0);
+            let ray    = Ray::new(origin, random_vector(&mut rng, 1.0) - origin * 0.25);
+            let max_t  = rng.rand_range(0.5, 8.0);
+
+            let record = object.trace(&ray, 1e-3, max_t);
+
+            assert_eq!(object.occluded(&ray, 1e-3, max_t), record.is_some(),
+                       "Occlusion of {} doesn't match its intersection.", name);
+
+            if let Some(record) = record {
+                let (u, v) = record.uv();
+
+                assert!((0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v),
+                        "Texture coordinates ({}, {}) of {} are out of range.", u, v, name);
+
+                hits += 1;
+            }
+        }
+
+        assert!(hits > 50, "Only {} rays hit the {}.", hits, name);
+    }
+}
+
+#[test]
+fn texture_coordinates_of_known_points() {
+    let material = Lambertian::new_solid(Vec3::fill(0.5));
+    let down     = Vec3::new(0.0, -1.0, 0.0);
+
+    // Faces of a box are parametrized by the following two axes.
+    let cuboid = Cuboid::new(Vec3::fill(-1.0), Vec3::fill(1.0), &material);
+    let record = cuboid.trace(&Ray::new(Vec3::new(0.5, 5.0, -0.5), down), 1e-3, f32::MAX)
+        .unwrap();
+
+    let (u, v) = record.uv();
+
+    assert!((u - 0.25).abs() < 1e-3 && (v - 0.75).abs() < 1e-3,
+            "Box top face has texture coordinates ({}, {}).", u, v);
+
+    // V goes from the center of a disc to its edge.
+    let disc   = Disc::new(Vec3::zero(), Vec3::new(0.0, 1.0, 0.0), 2.0, &material);
+    let record = disc.trace(&Ray::new(Vec3::new(1.0, 5.0, 0.0), down), 1e-3, f32::MAX)
+        .unwrap();
+
+    assert!((record.uv().1 - 0.5).abs() < 1e-3, "Disc hit has V {}.", record.uv().1);
+}
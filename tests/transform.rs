@@ -0,0 +1,126 @@
+//! Affine transforms and instances placed with them.
+
+use path_tracer::{Vec3, Ray};
+use path_tracer::material::Lambertian;
+use path_tracer::math::Mat4;
+use path_tracer::rng::Rng;
+use path_tracer::traceable::{Traceable, Sphere, Instance};
+
+use std::sync::Arc;
+
+fn random_vector(rng: &mut Rng, extent: f32) -> Vec3 {
+    Vec3::new(rng.rand_range(-extent, extent), rng.rand_range(-extent, extent),
+              rng.rand_range(-extent, extent))
+}
+
+fn distance(a: Vec3, b: Vec3) -> f32 {
+    (a - b).length()
+}
+
+/// Random composition of non-uniform scale (possibly mirroring), rotation, shear and
+/// translation.
+fn random_transform(rng: &mut Rng) -> Mat4 {
+    let scale = Vec3::new(rng.rand_range(0.2, 3.0), rng.rand_range(0.2, 3.0),
+                          rng.rand_range(-3.0, -0.2));
+    let axis  = random_vector(rng, 1.0) + Vec3::new(0.0, 0.0, 2.0);
+    let shear = Mat4::new([
+        [1.0, rng.rand_range(-1.0, 1.0), 0.0, 0.0],
+        [0.0, 1.0, rng.rand_range(-1.0, 1.0), 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    Mat4::translation(random_vector(rng, 10.0)) *
+        Mat4::rotation(axis.normalized(), rng.rand_range(-180.0, 180.0)) * shear *
+        Mat4::scale(scale)
+}
+
+#[test]
+fn inverse_undoes_transform() {
+    let mut rng = Rng::with_seed(1);
+
+    for _ in 0..200 {
+        let transform = random_transform(&mut rng);
+        let inverse   = transform.inverse().expect("Transform must be invertible.");
+
+        for &product in &[inverse * transform, transform * inverse] {
+            let points = [Vec3::zero(), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0),
+                          Vec3::new(0.0, 0.0, 1.0), random_vector(&mut rng, 5.0)];
+
+            for &point in &points {
+                let transformed = product.transform_point(point);
+
+                assert!(distance(transformed, point) < 1e-4,
+                        "{:?} times its inverse maps {:?} to {:?}.", transform, point.extract(),
+                        transformed.extract());
+            }
+        }
+    }
+
+    let flat = Mat4::scale(Vec3::new(1.0, 0.0, 1.0));
+
+    assert!(flat.inverse().is_none(), "Singular matrix must not have an inverse.");
+}
+
+/// Distance to the ellipsoid with `radii` centered at `center` along the normalized ray, if
+/// the ray hits it with a margin.
+fn ellipsoid_distance(ray: &Ray, center: Vec3, radii: Vec3) -> Option<f32> {
+    let origin    = (ray.origin - center) / radii;
+    let direction = ray.direction / radii;
+
+    let a = Vec3::dot(direction, direction);
+    let b = Vec3::dot(origin, direction);
+    let c = Vec3::dot(origin, origin) - 1.0;
+
+    let discriminant = b * b - a * c;
+
+    if discriminant > 1e-2 {
+        Some((-b - discriminant.sqrt()) / a)
+    } else {
+        None
+    }
+}
+
+#[test]
+fn instance_hits_scaled_sphere() {
+    let material = Lambertian::new_solid(Vec3::fill(0.5));
+    let sphere   = Arc::new(Sphere::new(Vec3::zero(), 1.0, &material));
+
+    let center   = Vec3::new(1.0, -2.0, -5.0);
+    let radii    = Vec3::new(2.0, 0.5, 1.0);
+    let instance = Instance::new(sphere, Mat4::translation(center) * Mat4::scale(radii));
+
+    let mut rng  = Rng::with_seed(2);
+    let mut hits = 0;
+
+    for _ in 0..2000 {
+        let origin = center + random_vector(&mut rng, 1.0).normalized() * 8.0;
+        let target = center + random_vector(&mut rng, 1.5);
+        let ray    = Ray::new(origin, target - origin);
+
+        let record = instance.trace(&ray, 1e-3, f32::MAX);
+
+        match (ellipsoid_distance(&ray, center, radii), record) {
+            (Some(t), Some(record)) => {
+                assert!((record.t - t).abs() < t * 1e-3,
+                        "Instance hit at distance {}, expected {}.", record.t, t);
+                assert!(distance(record.point, ray.point(t)) < 1e-2);
+
+                // Normal of the ellipsoid is the gradient of its implicit function.
+                let gradient = (record.point - center) / (radii * radii);
+
+                assert!(Vec3::dot(record.normal, gradient.normalized()) > 0.999,
+                        "Normal {:?} doesn't match the ellipsoid.", record.normal.extract());
+
+                hits += 1;
+            }
+            (None, Some(record)) if ellipsoid_distance(&ray, center, radii * 1.01).is_none() => {
+                panic!("Instance hit at distance {} where the ellipsoid is missed.", record.t);
+            }
+            (Some(t), None) => panic!("Instance missed, expected hit at distance {}.", t),
+            _ => (),
+        }
+    }
+
+    assert!(hits > 500, "Only {} rays hit the instance.", hits);
+}
@@ -0,0 +1,71 @@
+//! White furnace test: a white diffuse sphere inside a uniformly emitting box must look
+//! exactly as bright as the box itself. Any deviation of the image brightness from the
+//! emitted radiance is energy lost or gained by the renderer.
+
+use path_tracer::Vec3;
+use path_tracer::math::Camera;
+use path_tracer::material::{Lambertian, DiffuseLight};
+use path_tracer::raytracer::Raytracer;
+use path_tracer::scene::Scene;
+use path_tracer::traceable::{Sphere, TriangleMesh};
+
+const EMISSION: f32 = 1.0;
+
+fn furnace() -> Scene {
+    let mut scene = Scene::new();
+
+    let light = DiffuseLight::new(Vec3::fill(EMISSION));
+    let white = Lambertian::new_solid(Vec3::fill(1.0));
+
+    let positions = (0..8)
+        .map(|i| {
+            let coordinate = |bit: usize| if i & bit != 0 { 2.0 } else { -2.0 };
+
+            Vec3::new(coordinate(1), coordinate(2), coordinate(4))
+        })
+        .collect();
+
+    let indices = vec![
+        [0, 4, 6], [0, 6, 2],
+        [1, 3, 7], [1, 7, 5],
+        [0, 1, 5], [0, 5, 4],
+        [2, 6, 7], [2, 7, 3],
+        [0, 2, 3], [0, 3, 1],
+        [4, 5, 7], [4, 7, 6],
+    ];
+
+    scene.add_mesh(&TriangleMesh::new(positions, None, None, indices, &light));
+    scene.add(Sphere::new(Vec3::zero(), 1.0, &white));
+
+    scene
+}
+
+#[test]
+fn white_furnace() {
+    const SIZE:    usize = 48;
+    const SAMPLES: usize = 16;
+
+    // Noise of the estimate is about 2e-4. The default approximate normalization has relative
+    // error up to 4e-4 per direction, which adds up over the bounces of a path.
+    let (mode, tolerance) = if cfg!(feature = "precise") {
+        ("precise", 1e-3)
+    } else {
+        ("default", 2e-3)
+    };
+
+    let camera = Camera::new(Vec3::new(0.3, 0.4, 1.9), Vec3::zero(), Vec3::new(0.0, 1.0, 0.0),
+                             70.0, SIZE, SIZE);
+
+    let image = Raytracer::new(camera, furnace(), SAMPLES, 1).render_image();
+
+    let sum: f64 = image.iter()
+        .flatten()
+        .map(|&channel| channel as f64)
+        .sum();
+
+    let mean  = sum / (image.len() * 3) as f64;
+    let error = (mean - EMISSION as f64) / EMISSION as f64;
+
+    assert!(error.abs() < tolerance, "Energy error {:+.3e} in {} mode exceeds {:.0e}.", error,
+            mode, tolerance);
+}